@@ -0,0 +1,91 @@
+use criterion::{criterion_group, criterion_main, Benchmark, Criterion, Throughput};
+
+use sim::{
+    game::{Edge, EdgeState, GameState, Outcome},
+    notation,
+    player::{MiniMax, Player, Random},
+};
+
+
+/// Positions with 3, 5 and 8 colored edges, used to benchmark the search.
+const POSITIONS: &[(&str, &str)] = &[
+    ("depth 3", "0-1 2-3 4-5"),
+    ("depth 5", "0-1 2-3 4-5 0-2 1-3"),
+    ("depth 8", "0-1 2-3 4-5 0-2 1-3 0-4 1-5 3-5"),
+];
+
+fn position(moves: &str) -> GameState {
+    notation::parse_position(moves).expect("invalid benchmark position")
+}
+
+fn game_state(c: &mut Criterion) {
+    let state = position(POSITIONS[2].1);
+
+    c.bench_function("would_create_triangle", move |b| {
+        b.iter(|| {
+            Edge::all_edges()
+                .filter(|&e| state.would_create_triangle(e, EdgeState::Red))
+                .count()
+        })
+    });
+
+    let state = position(POSITIONS[2].1);
+    c.bench_function("safe_moves", move |b| {
+        b.iter(|| state.safe_moves(EdgeState::Red).count())
+    });
+
+    c.bench_function("set_edge/edge_state", |b| {
+        let mut state = GameState::new();
+        b.iter(|| {
+            for e in Edge::all_edges() {
+                state.set_edge(e, EdgeState::Blue);
+            }
+            for e in Edge::all_edges() {
+                state.set_edge(e, EdgeState::None);
+            }
+            Edge::all_edges().filter(|&e| state.edge_state(e).is_none()).count()
+        })
+    });
+}
+
+fn minimax(c: &mut Criterion) {
+    for &(name, moves) in POSITIONS {
+        let state = position(moves);
+
+        // Search once to know how many positions are expanded per move. With
+        // that, criterion reports the number of positions per second.
+        let mut player = MiniMax::new(state.to_move(), 0);
+        player.set_verbose(false);
+        player.next_move(&state).unwrap();
+        let nodes = player.nodes();
+        println!("minimax {}: {} positions per move", name, nodes);
+
+        let benchmark = Benchmark::new(name, move |b| {
+            b.iter(|| player.next_move(&state).unwrap())
+        });
+        c.bench("minimax", benchmark.throughput(Throughput::Elements(nodes as u32)));
+    }
+}
+
+fn random_games(c: &mut Criterion) {
+    c.bench_function("random game", |b| {
+        b.iter(|| {
+            let mut red = Random::new(EdgeState::Red, 1);
+            let mut blue = Random::new(EdgeState::Blue, 2);
+            let mut state = GameState::new();
+            loop {
+                let player = match state.to_move() {
+                    EdgeState::Blue => &mut blue,
+                    _ => &mut red,
+                };
+                let edge = player.next_move(&state).unwrap();
+                if let Outcome::Won(winner) = state.play(edge).unwrap() {
+                    break winner;
+                }
+            }
+        })
+    });
+}
+
+criterion_group!(benches, game_state, minimax, random_games);
+criterion_main!(benches);
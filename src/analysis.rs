@@ -0,0 +1,306 @@
+//! Perfect play analysis of Sim positions.
+//!
+//! Sim cannot end in a draw and the game tree is small enough to be searched
+//! completely (with a transposition table), so we can tell for every move
+//! whether it wins or loses under perfect play.
+
+use std::collections::HashMap;
+
+use crate::game::{GameState, Edge, EdgeState};
+
+
+/// The result of a move when both players play perfectly afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Win,
+    Loss,
+}
+
+/// The evaluation of a single move in a position.
+#[derive(Clone, Debug)]
+pub struct MoveEval {
+    /// The evaluated move.
+    pub edge: Edge,
+
+    /// Whether the player making this move wins or loses with perfect play.
+    pub verdict: Verdict,
+
+    /// Number of plies until the game ends (including this move). The winning
+    /// player tries to end the game as early as possible, the losing player
+    /// tries to delay the end as long as possible.
+    pub plies: u8,
+
+    /// The principal variation: the sequence of moves (starting with `edge`)
+    /// that is played when both players play perfectly.
+    pub pv: Vec<Edge>,
+}
+
+/// Evaluates every uncolored edge in `state` for the player `to_move`.
+///
+/// The returned list is sorted from best to worst move: first all winning
+/// moves (fastest win first), then all losing moves (slowest loss first).
+pub fn analyze(state: &GameState, to_move: EdgeState) -> Vec<MoveEval> {
+    let mut solver = Solver::new();
+
+    let mut evals = state.legal_moves()
+        .map(|edge| {
+            let (wins, plies) = solver.eval_move(state, edge, to_move);
+
+            // Follow the best moves of both players to get the principal
+            // variation.
+            let mut pv = vec![edge];
+            let mut pos = state.clone();
+            let mut color = to_move;
+            let mut last = edge;
+            while !pos.would_create_triangle(last, color) {
+                pos.set_edge(last, color);
+                color = color.opponent();
+                match solver.solve(&pos, color).best {
+                    Some(next) => {
+                        pv.push(next);
+                        last = next;
+                    }
+                    None => break,
+                }
+            }
+
+            MoveEval {
+                edge,
+                verdict: if wins { Verdict::Win } else { Verdict::Loss },
+                plies,
+                pv,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    evals.sort_by_key(|eval| match eval.verdict {
+        Verdict::Win => eval.plies as i16,
+        Verdict::Loss => 100 - eval.plies as i16,
+    });
+    evals
+}
+
+/// Prints the evaluation of all possible moves in `state` to stdout,
+/// including the outcome of the game under perfect play and the principal
+/// variation.
+pub fn print_analysis(state: &GameState, to_move: EdgeState) {
+    println!("{:?} to move", to_move);
+
+    let evals = analyze(state, to_move);
+    let best = match evals.first() {
+        Some(best) => best,
+        None => {
+            println!("No moves left");
+            return;
+        }
+    };
+
+    let winner = match best.verdict {
+        Verdict::Win => to_move,
+        Verdict::Loss => to_move.opponent(),
+    };
+    println!("Result: {:?} wins with perfect play (after {} plies)", winner, best.plies);
+
+    println!("Moves:");
+    for eval in &evals {
+        let marker = if eval.verdict == best.verdict && eval.plies == best.plies {
+            '*'
+        } else {
+            ' '
+        };
+        let verdict = match eval.verdict {
+            Verdict::Win => "wins",
+            Verdict::Loss => "loses",
+        };
+        println!("  {} {}  {:<5} in {:>2} plies", marker, eval.edge, verdict, eval.plies);
+    }
+
+    let pv = best.pv.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    println!("Principal variation: {}", pv.join(" "));
+}
+
+/// The result of searching a position, from the view of the player to move.
+#[derive(Clone, Copy)]
+struct Eval {
+    wins: bool,
+    plies: u8,
+    best: Option<Edge>,
+}
+
+/// A full-depth negamax search with a transposition table. Searching many
+/// positions with the same solver reuses the results of earlier searches.
+#[derive(Default)]
+pub struct Solver {
+    cache: HashMap<(GameState, EdgeState), Eval>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the best move for `to_move` in `state`, whether it wins and
+    /// how many plies the game lasts (see `MoveEval`). Returns `None` if
+    /// there are no legal moves.
+    pub fn best_move(
+        &mut self,
+        state: &GameState,
+        to_move: EdgeState,
+    ) -> Option<(Edge, Verdict, u8)> {
+        let eval = self.solve(state, to_move);
+        let verdict = if eval.wins { Verdict::Win } else { Verdict::Loss };
+        eval.best.map(|edge| (edge, verdict, eval.plies))
+    }
+
+    /// Returns whether `color` wins by coloring `edge` in `state` and how many
+    /// plies the game lasts from there on.
+    fn eval_move(&mut self, state: &GameState, edge: Edge, color: EdgeState) -> (bool, u8) {
+        if state.would_create_triangle(edge, color) {
+            return (false, 1);
+        }
+
+        let mut next = state.clone();
+        next.set_edge(edge, color);
+        let reply = self.solve(&next, color.opponent());
+        (!reply.wins, reply.plies + 1)
+    }
+
+    /// Searches the position `state` with `to_move` being the player to move.
+    fn solve(&mut self, state: &GameState, to_move: EdgeState) -> Eval {
+        if let Some(eval) = self.cache.get(&(state.clone(), to_move)) {
+            return *eval;
+        }
+
+        // If there are no moves left, we say the player to move lost. This
+        // can't happen in real games as there is always a triangle after all
+        // edges are colored.
+        let mut best = Eval { wins: false, plies: 0, best: None };
+        for edge in state.legal_moves() {
+            let (wins, plies) = self.eval_move(state, edge, to_move);
+            let better = match (best.best, wins, best.wins) {
+                (None, _, _) => true,
+                (_, true, false) => true,
+                (_, false, true) => false,
+                (_, true, true) => plies < best.plies,
+                (_, false, false) => plies > best.plies,
+            };
+
+            if better {
+                best = Eval { wins, plies, best: Some(edge) };
+            }
+        }
+
+        self.cache.insert((state.clone(), to_move), best);
+        best
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::seq::IteratorRandom;
+
+    use crate::{notation::parse_position_string, player::seeded_rng};
+    use super::*;
+
+    /// Searches `state` without a transposition table. Returns whether the
+    /// player to move wins and after how many plies.
+    fn uncached(state: &GameState, to_move: EdgeState) -> (bool, u8) {
+        let mut best: Option<(bool, u8)> = None;
+        for edge in state.legal_moves() {
+            let eval = if state.would_create_triangle(edge, to_move) {
+                (false, 1)
+            } else {
+                let mut next = state.clone();
+                next.set_edge(edge, to_move);
+                let (wins, plies) = uncached(&next, to_move.opponent());
+                (!wins, plies + 1)
+            };
+
+            let better = match (best, eval) {
+                (None, _) => true,
+                (Some((a, _)), (b, _)) if a != b => b,
+                (Some((true, plies)), (true, new)) => new < plies,
+                (Some((_, plies)), (_, new)) => new > plies,
+            };
+            if better {
+                best = Some(eval);
+            }
+        }
+        best.unwrap_or((false, 0))
+    }
+
+    #[test]
+    fn forced_loss() {
+        // The only uncolored edge, 0-1, completes the red triangle 0-1-2.
+        let state = parse_position_string(".rrbbrrbbbrbbrr").unwrap();
+        let evals = analyze(&state, EdgeState::Red);
+        assert_eq!(evals.len(), 1);
+        assert_eq!(evals[0].edge, Edge::new(0));
+        assert_eq!(evals[0].verdict, Verdict::Loss);
+        assert_eq!(evals[0].plies, 1);
+        assert_eq!(evals[0].pv, vec![Edge::new(0)]);
+    }
+
+    #[test]
+    fn win_for_side_to_move() {
+        // Blue wins by coloring 3-5, after which red has to color 0-5 and
+        // complete the triangle 0-1-5. Coloring 0-5 loses for blue.
+        let state = parse_position_string("rbbr.rbbrrbbr.r").unwrap();
+        assert_eq!(state.to_move(), EdgeState::Blue);
+
+        let evals = analyze(&state, EdgeState::Blue);
+        assert_eq!(evals.len(), 2);
+        assert_eq!(evals[0].edge, Edge::new(13));
+        assert_eq!(evals[0].verdict, Verdict::Win);
+        assert_eq!(evals[0].plies, 2);
+        assert_eq!(evals[0].pv, vec![Edge::new(13), Edge::new(4)]);
+        assert_eq!(evals[1].verdict, Verdict::Loss);
+
+        let mut solver = Solver::new();
+        assert_eq!(
+            solver.best_move(&state, EdgeState::Blue),
+            Some((Edge::new(13), Verdict::Win, 2)),
+        );
+    }
+
+    #[test]
+    fn cache_matches_uncached_search() {
+        let mut rng = seeded_rng(27);
+
+        // One solver for all positions, so that later searches use the
+        // results cached by earlier ones.
+        let mut solver = Solver::new();
+        for _ in 0..30 {
+            // Random positions with 8 uncolored edges and no triangle.
+            let mut state = GameState::new();
+            while state.count(EdgeState::None) > 8 {
+                let color = state.to_move();
+                match state.safe_moves(color).choose(&mut rng) {
+                    Some(edge) => {
+                        state.play(edge).unwrap();
+                    }
+                    None => state = GameState::new(),
+                }
+            }
+
+            let to_move = state.to_move();
+            let (wins, plies) = uncached(&state, to_move);
+            let (_, verdict, best_plies) = solver.best_move(&state, to_move).unwrap();
+            assert_eq!(verdict == Verdict::Win, wins);
+            assert_eq!(best_plies, plies);
+
+            for eval in analyze(&state, to_move) {
+                let mut next = state.clone();
+                let expected = if next.would_create_triangle(eval.edge, to_move) {
+                    (false, 1)
+                } else {
+                    next.set_edge(eval.edge, to_move);
+                    let (wins, plies) = uncached(&next, to_move.opponent());
+                    (!wins, plies + 1)
+                };
+                assert_eq!((eval.verdict == Verdict::Win, eval.plies), expected);
+            }
+        }
+    }
+}
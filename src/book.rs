@@ -0,0 +1,298 @@
+//! Opening books: precomputed best moves for early positions.
+//!
+//! Positions that only differ by renaming the vertices (there are 720 ways to
+//! do that) or by swapping the colors are equivalent, so the book only stores
+//! one canonical representative of each class of positions.
+//!
+//! A book file contains one position per line: `<position> <edge> <verdict>
+//! <plies>`. The position is written from the view of the player to move
+//! (see `notation`), `<edge>` is the best move in that position, `<verdict>`
+//! is `win` or `loss` and `<plies>` the number of plies until the game ends
+//! with perfect play (see `analysis::MoveEval`). The file is a data file (see
+//! `io`).
+
+use std::{collections::{HashMap, HashSet}, path::Path};
+
+use crate::{
+    analysis::{Solver, Verdict},
+    game::{GameState, Edge, EdgeState, Vertex},
+    io::{self, DataFile},
+    notation::{parse_edge, parse_view, view_to_string},
+};
+
+
+/// A book move, as returned by `OpeningBook::lookup`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookEntry {
+    pub edge: Edge,
+    pub verdict: Verdict,
+    pub plies: u8,
+}
+
+/// A position from the view of the player to move: the edges of the player
+/// to move and the edges of the opponent.
+type Key = (u16, u16);
+
+/// Maps canonical positions to the best move in that position.
+#[derive(Clone, Debug)]
+pub struct OpeningBook {
+    /// For every renaming of the vertices: the id of the edge every edge is
+    /// mapped to.
+    symmetries: Vec<[u8; 15]>,
+
+    /// Book moves of canonical positions (in canonical edge ids).
+    entries: HashMap<Key, BookEntry>,
+}
+
+impl Default for OpeningBook {
+    fn default() -> Self {
+        Self {
+            symmetries: edge_symmetries(),
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl OpeningBook {
+    /// Creates an empty book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates a book containing all positions with less than `max_plies`
+    /// colored edges that can occur in a game. Book moves are computed by
+    /// the perfect play solver.
+    pub fn generate(max_plies: u8) -> Self {
+        let mut out = Self::new();
+        let mut solver = Solver::new();
+
+        let mut level = HashSet::new();
+        level.insert((0, 0));
+        for _ in 0..max_plies {
+            let mut next_level = HashSet::new();
+            for &(own, other) in &level {
+                let state = state_from_masks(own, other);
+                let (edge, verdict, plies) = match solver.best_move(&state, EdgeState::Red) {
+                    Some(best) => best,
+                    None => continue,
+                };
+                out.entries.insert((own, other), BookEntry { edge, verdict, plies });
+
+                // Moves that complete a triangle end the game, so they don't
+                // lead to further book positions.
+                for edge in state.safe_moves(EdgeState::Red) {
+                    next_level.insert(out.canonical((other, own | edge.bit())).0);
+                }
+            }
+            level = next_level;
+        }
+
+        out
+    }
+
+    /// The number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the book move for `to_move` in `state`, or `None` if the
+    /// position is not in the book.
+    pub fn lookup(&self, state: &GameState, to_move: EdgeState) -> Option<BookEntry> {
+        let key = (state.mask(to_move), state.mask(to_move.opponent()));
+        let (canonical, symmetry) = self.canonical(key);
+        let entry = self.entries.get(&canonical)?;
+
+        // Map the canonical move back to the actual position.
+        let map = &self.symmetries[symmetry];
+        let id = map.iter().position(|&id| id == entry.edge.id())?;
+        Some(BookEntry {
+            edge: Edge::new(id as u8),
+            ..*entry
+        })
+    }
+
+    /// Reads a book file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = DataFile::read(path)?;
+        let mut out = Self::new();
+        for line in file.lines() {
+            let invalid = || line.invalid();
+            let fields = line.text.split_whitespace().collect::<Vec<_>>();
+            if fields.len() != 4 {
+                return Err(invalid());
+            }
+
+            let key = parse_view(fields[0]).map_err(|_| invalid())?;
+
+            let entry = BookEntry {
+                edge: parse_edge(fields[1]).map_err(|_| invalid())?,
+                verdict: match fields[2] {
+                    "win" => Verdict::Win,
+                    "loss" => Verdict::Loss,
+                    _ => return Err(invalid()),
+                },
+                plies: fields[3].parse().map_err(|_| invalid())?,
+            };
+
+            // Books written by other versions might not use the same
+            // canonical representatives, so we canonicalize again.
+            let (canonical, symmetry) = out.canonical(key);
+            let edge = Edge::new(out.symmetries[symmetry][entry.edge.id() as usize]);
+            out.entries.insert(canonical, BookEntry { edge, ..entry });
+        }
+
+        Ok(out)
+    }
+
+    /// Writes the book to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|&(&(own, other), _)| ((own | other).count_ones(), own, other));
+
+        let mut out = String::from("# Sim opening book: <position> <edge> <verdict> <plies>\n");
+        for (&(own, other), entry) in entries {
+            let position = view_to_string(own, other);
+            let verdict = match entry.verdict {
+                Verdict::Win => "win",
+                Verdict::Loss => "loss",
+            };
+            out += &format!("{} {} {} {}\n", position, entry.edge, verdict, entry.plies);
+        }
+
+        io::write(path, &out)
+    }
+
+    /// Returns the canonical representative of `key` (the smallest key of
+    /// all equivalent positions) and the index of the symmetry that maps
+    /// `key` to it.
+    fn canonical(&self, key: Key) -> (Key, usize) {
+        self.symmetries.iter()
+            .map(|map| (map_mask(key.0, map), map_mask(key.1, map)))
+            .enumerate()
+            .map(|(i, key)| (key, i))
+            .min()
+            .expect("no symmetries")
+    }
+}
+
+/// Returns a state where red owns the edges in `own` and blue the ones in
+/// `other`.
+fn state_from_masks(own: u16, other: u16) -> GameState {
+    let mut state = GameState::new();
+    for edge in Edge::all_edges() {
+        if own & edge.bit() != 0 {
+            state.set_edge(edge, EdgeState::Red);
+        } else if other & edge.bit() != 0 {
+            state.set_edge(edge, EdgeState::Blue);
+        }
+    }
+    state
+}
+
+/// Applies an edge mapping to a mask of edges.
+fn map_mask(mask: u16, map: &[u8; 15]) -> u16 {
+    Edge::all_edges()
+        .filter(|edge| mask & edge.bit() != 0)
+        .fold(0, |out, edge| out | 1 << map[edge.id() as usize])
+}
+
+/// Returns how edges are mapped for each of the 720 permutations of the
+/// vertices.
+fn edge_symmetries() -> Vec<[u8; 15]> {
+    fn permutations(prefix: &mut Vec<u8>, out: &mut Vec<[u8; 15]>) {
+        if prefix.len() == 6 {
+            let mut map = [0; 15];
+            for edge in Edge::all_edges() {
+                let (a, b) = edge.endpoints();
+                let a = Vertex::new(prefix[a.id() as usize]);
+                let b = Vertex::new(prefix[b.id() as usize]);
+                map[edge.id() as usize] = Edge::between(a, b).id();
+            }
+            out.push(map);
+            return;
+        }
+
+        for v in 0..6 {
+            if !prefix.contains(&v) {
+                prefix.push(v);
+                permutations(prefix, out);
+                prefix.pop();
+            }
+        }
+    }
+
+    let mut out = Vec::with_capacity(720);
+    permutations(&mut Vec::new(), &mut out);
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::notation::parse_position_string;
+    use super::*;
+
+    #[test]
+    fn symmetries() {
+        let symmetries = edge_symmetries();
+        assert_eq!(symmetries.len(), 720);
+        assert_eq!(symmetries.iter().collect::<HashSet<_>>().len(), 720);
+        assert!(symmetries.contains(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]));
+        for map in &symmetries {
+            let mut ids = map.to_vec();
+            ids.sort_unstable();
+            assert_eq!(ids, (0..15).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn canonical_key_of_all_images() {
+        let book = OpeningBook::new();
+        for position in &["rbbr.rbbrrbbr.r", "rb.r..b........", "r......b.....rb"] {
+            let state = parse_position_string(position).unwrap();
+            let key = (state.mask(EdgeState::Red), state.mask(EdgeState::Blue));
+            let (canonical, symmetry) = book.canonical(key);
+            let map = &book.symmetries[symmetry];
+            assert_eq!((map_mask(key.0, map), map_mask(key.1, map)), canonical);
+
+            for map in &book.symmetries {
+                let image = (map_mask(key.0, map), map_mask(key.1, map));
+                assert_eq!(book.canonical(image).0, canonical);
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_transformed_position() {
+        // Blue to move wins by coloring edge 13 (see the analysis tests).
+        let state = parse_position_string("rbbr.rbbrrbbr.r").unwrap();
+        let (own, other) = (state.mask(EdgeState::Blue), state.mask(EdgeState::Red));
+        let content = format!("{} 3-5 win 2\n", view_to_string(own, other));
+        let path = std::env::temp_dir().join(format!("sim-book-{}", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let book = OpeningBook::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(book.len(), 1);
+
+        let mut solver = Solver::new();
+        for map in &book.symmetries {
+            let (own, other) = (map_mask(own, map), map_mask(other, map));
+            let expected = BookEntry { edge: Edge::new(map[13]), verdict: Verdict::Win, plies: 2 };
+
+            // Both colors can be the player to move.
+            let blue_to_move = state_from_masks(other, own);
+            assert_eq!(book.lookup(&blue_to_move, EdgeState::Blue), Some(expected));
+            let red_to_move = state_from_masks(own, other);
+            assert_eq!(book.lookup(&red_to_move, EdgeState::Red), Some(expected));
+
+            let (edge, verdict, plies) = solver.best_move(&red_to_move, EdgeState::Red).unwrap();
+            assert_eq!((edge, verdict, plies), (expected.edge, Verdict::Win, 2));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Time controls: how much time the players have for their moves.
+//!
+//! A time control is written as `<total>+<increment>` (e.g. `60+2`: 60
+//! seconds for the whole game plus 2 seconds per move made), `<total>`
+//! (without increment) or `<seconds>/move` (a fixed time for every move).
+//! All times are given in seconds and may have a fractional part.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::game::{EdgeState, GameState};
+
+
+/// The time a player may spend on their moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    /// A total time for the whole game, which grows by `increment` after
+    /// every move of the player.
+    Total { total: Duration, increment: Duration },
+
+    /// A fixed time for every move. Unused time is not carried over.
+    PerMove(Duration),
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!(
+            "invalid time control '{}' (expected e.g. '60', '60+2' or '5/move')",
+            input,
+        );
+        let seconds = |s: &str| {
+            let secs = s.trim().parse::<f64>().map_err(|_| invalid())?;
+            Duration::try_from_secs_f64(secs).map_err(|e| format!("{}: {}", invalid(), e))
+        };
+
+        if let Some(per_move) = input.strip_suffix("/move") {
+            return Ok(TimeControl::PerMove(seconds(per_move)?));
+        }
+        let (total, increment) = match input.split_once('+') {
+            Some((total, increment)) => (seconds(total)?, seconds(increment)?),
+            None => (seconds(input)?, Duration::ZERO),
+        };
+        Ok(TimeControl::Total { total, increment })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeControl::Total { total, increment } if increment.is_zero() => {
+                write!(f, "{}", total.as_secs_f64())
+            }
+            TimeControl::Total { total, increment } => {
+                write!(f, "{}+{}", total.as_secs_f64(), increment.as_secs_f64())
+            }
+            TimeControl::PerMove(per_move) => write!(f, "{}/move", per_move.as_secs_f64()),
+        }
+    }
+}
+
+/// The clocks of both players during a game.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    control: TimeControl,
+
+    /// The time left for the next move of red and blue.
+    remaining: [Duration; 2],
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let start = match control {
+            TimeControl::Total { total, .. } => total,
+            TimeControl::PerMove(per_move) => per_move,
+        };
+
+        Self {
+            control,
+            remaining: [start; 2],
+        }
+    }
+
+    /// Returns the time `color` has left for its next move before losing on
+    /// time.
+    pub fn remaining(&self, color: EdgeState) -> Duration {
+        self.remaining[index(color)]
+    }
+
+    /// Returns how much time `color` should spend at most on its next move
+    /// in `state`: a share of the remaining time that leaves enough for the
+    /// rest of the game and some safety margin.
+    pub fn move_budget(&self, color: EdgeState, state: &GameState) -> Duration {
+        let remaining = self.remaining(color);
+        let safe = remaining.mul_f64(0.8);
+        match self.control {
+            TimeControl::PerMove(_) => safe,
+            TimeControl::Total { increment, .. } => {
+                // The player makes at most every other of the remaining moves.
+                let moves_left = state.legal_mask().count_ones().div_ceil(2).max(1);
+                (remaining / moves_left).saturating_add(increment).min(safe)
+            }
+        }
+    }
+
+    /// Charges `elapsed` to the clock of `color` after it made a move. Returns
+    /// `false` if the player ran out of time.
+    pub fn record_move(&mut self, color: EdgeState, elapsed: Duration) -> bool {
+        let remaining = &mut self.remaining[index(color)];
+        if elapsed > *remaining {
+            *remaining = Duration::ZERO;
+            return false;
+        }
+
+        *remaining = match self.control {
+            TimeControl::Total { increment, .. } => {
+                (*remaining - elapsed).saturating_add(increment)
+            }
+            TimeControl::PerMove(per_move) => per_move,
+        };
+        true
+    }
+}
+
+fn index(color: EdgeState) -> usize {
+    match color {
+        EdgeState::Blue => 1,
+        _ => 0,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let total = |total, increment| TimeControl::Total {
+            total: Duration::from_secs_f64(total),
+            increment: Duration::from_secs_f64(increment),
+        };
+        assert_eq!("60".parse(), Ok(total(60.0, 0.0)));
+        assert_eq!("1.5+0.5".parse(), Ok(total(1.5, 0.5)));
+        assert_eq!("5/move".parse(), Ok(TimeControl::PerMove(Duration::from_secs(5))));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for input in &["", "abc", "-1", "NaN", "inf", "1e30", "60+1e30", "1e30/move", "5/turn"] {
+            assert!(input.parse::<TimeControl>().is_err(), "'{}' was accepted", input);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Tuning the weights of the heuristic player with a genetic algorithm.
+//!
+//! Every generation, all candidates play against each other (with both
+//! colors). The fitness of a candidate is the fraction of its games it won.
+//! The better half survives and the other half is replaced by children of
+//! the survivors (uniform crossover plus gaussian mutation).
+
+use rand::{
+    Rng,
+    distributions::{Distribution, Normal},
+};
+
+use crate::{
+    game::EdgeState,
+    heuristic::{Weights, NUM_FEATURES},
+    player::{Heuristic, SeededRng, derive_seeds, seeded_rng},
+    runner::play_match,
+};
+
+
+/// Settings for `evolve`.
+#[derive(Clone, Copy, Debug)]
+pub struct EvolveConfig {
+    /// The number of candidates in each generation (at least 2).
+    pub population: usize,
+
+    /// The number of generations to evaluate.
+    pub generations: u32,
+
+    /// The number of games per pairing and color.
+    pub games: u32,
+
+    /// The standard deviation of the noise added to children's weights
+    /// (finite and not negative).
+    pub mutation: f64,
+
+    /// Seed for all random decisions.
+    pub seed: u64,
+}
+
+/// Summary of one evaluated generation, passed to the progress callback of
+/// `evolve`.
+#[derive(Clone, Debug)]
+pub struct Generation {
+    /// Starts at 1.
+    pub number: u32,
+    pub best: Weights,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+}
+
+/// Evolves weights starting from `start` and returns the best weights of the
+/// last generation. `on_generation` is called after each generation is
+/// evaluated.
+pub fn evolve(
+    start: Weights,
+    config: EvolveConfig,
+    mut on_generation: impl FnMut(&Generation),
+) -> Weights {
+    assert!(config.population >= 2, "population must contain at least 2 candidates");
+    assert!(
+        config.mutation.is_finite() && config.mutation >= 0.0,
+        "mutation must be finite and not negative",
+    );
+
+    let mut rng = seeded_rng(config.seed);
+    let noise = Normal::new(0.0, config.mutation);
+    let mutate = |weights: &mut Weights, rng: &mut SeededRng| {
+        for w in weights.0.iter_mut() {
+            *w += noise.sample(rng);
+        }
+    };
+
+    // The first generation consists of the start weights and mutations of
+    // them.
+    let mut population = vec![start];
+    while population.len() < config.population {
+        let mut weights = start;
+        mutate(&mut weights, &mut rng);
+        population.push(weights);
+    }
+
+    let mut best = start;
+    for number in 1..=config.generations {
+        let fitness = evaluate(&population, config.games, &mut rng);
+        let mut ranked = population.into_iter().zip(fitness).collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+        best = ranked[0].0;
+        on_generation(&Generation {
+            number,
+            best,
+            best_fitness: ranked[0].1,
+            mean_fitness: ranked.iter().map(|(_, f)| f).sum::<f64>() / ranked.len() as f64,
+        });
+
+        // Keep the better half and fill up with their children.
+        let survivors = ranked.into_iter()
+            .take(config.population.div_ceil(2))
+            .map(|(weights, _)| weights)
+            .collect::<Vec<_>>();
+        population = survivors.clone();
+        while population.len() < config.population {
+            let a = &survivors[rng.gen_range(0, survivors.len())];
+            let b = &survivors[rng.gen_range(0, survivors.len())];
+            let mut child = Weights([0.0; NUM_FEATURES]);
+            for i in 0..NUM_FEATURES {
+                child.0[i] = if rng.gen() { a.0[i] } else { b.0[i] };
+            }
+            mutate(&mut child, &mut rng);
+            population.push(child);
+        }
+    }
+
+    best
+}
+
+/// Lets every candidate play against every other one and returns the
+/// fraction of games each candidate won.
+fn evaluate(population: &[Weights], games: u32, rng: &mut SeededRng) -> Vec<f64> {
+    let mut wins = vec![0; population.len()];
+    let mut played = vec![0; population.len()];
+
+    for (i, &red_weights) in population.iter().enumerate() {
+        for (j, &blue_weights) in population.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+
+            let (seed_red, seed_blue) = derive_seeds(rng.gen());
+            let mut red = Heuristic::with_weights(EdgeState::Red, seed_red, red_weights);
+            let mut blue = Heuristic::with_weights(EdgeState::Blue, seed_blue, blue_weights);
+            let results = play_match(&mut red, &mut blue, games, None);
+
+            for result in results {
+                match result.winner {
+                    EdgeState::Red => wins[i] += 1,
+                    _ => wins[j] += 1,
+                }
+                played[i] += 1;
+                played[j] += 1;
+            }
+        }
+    }
+
+    wins.iter().zip(&played).map(|(&w, &p)| w as f64 / p.max(1) as f64).collect()
+}
@@ -1,74 +1,360 @@
 //! Types and functions describing the core game.
 
-/// Stores the state of a Sim game (the state of all 15 edges).
-#[derive(Clone)]
+use std::{convert::TryFrom, error, fmt};
+
+/// Stores the state of a Sim game (the state of all 15 edges, the player to
+/// move and whether the game is already over).
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct GameState {
-    /// We use a very space effient way to store the game state.
-    ///
-    /// We have 15 edges and each edge can be in one of three states. Thus we
-    /// need only 2 bits for each edge. This means we can fit all data into a
-    /// `u32`. The LSB store the state of the edge with ID 0. The two
-    /// upppermost bits are always 0.
+    /// We store the edges as two bitboards: one `u16` per color, where bit
+    /// `i` is set if the edge with ID `i` has that color. The uppermost bit
+    /// is always 0 and an edge is never set in both masks.
     ///
-    /// Bit:     31   30   29   28   27   26   …    3    2    1    0
-    ///        ┌────┬────┬────┬────┬────┬────┬───┬────┬────┬────┬────┐
-    /// Edge:  │ __ │ __ │ 14 │ 14 │ 13 │ 13 │ … │  1 │  1 │  0 │  0 │
-    ///        └────┴────┴────┴────┴────┴────┴───┴────┴────┴────┴────┘
+    /// Bit:     15   14   13   …    2    1    0
+    ///        ┌────┬────┬────┬───┬────┬────┬────┐
+    /// Edge:  │ __ │ 14 │ 13 │ … │  2 │  1 │  0 │
+    ///        └────┴────┴────┴───┴────┴────┴────┘
     ///
-    encoded: u32,
+    /// With this, most questions about the game (e.g. "does this move create
+    /// a triangle?") can be answered with a few bitwise operations using the
+    /// precomputed triangle masks (see `TRIANGLE_MASKS`).
+    red: u16,
+    blue: u16,
+
+    /// The player who makes the next move. Only updated by `play`, not by
+    /// `set_edge`.
+    to_move: EdgeState,
+
+    /// The winner of the game, if the game is over.
+    winner: Option<EdgeState>,
 }
 
 impl GameState {
-    /// Returns a new game where all edges are `None`.
+    /// Returns a new game where all edges are `None` and red makes the first
+    /// move.
     pub fn new() -> Self {
         Self {
-            encoded: 0,
+            red: 0,
+            blue: 0,
+            to_move: EdgeState::Red,
+            winner: None,
+        }
+    }
+
+    /// Creates a position from the colors of all 15 edges (in order of their
+    /// IDs). `first` is the player who made the first move and has to be
+    /// `Red` or `Blue` (`SimError::InvalidFirstPlayer` otherwise).
+    ///
+    /// The player to move is derived from the number of red and blue edges:
+    /// `first` if both numbers are equal, the other player otherwise. A
+    /// triangle is allowed if it was completed by the last move (the game is
+    /// then over). Returns an error if the numbers of edges are inconsistent,
+    /// if the position contains triangles of both colors or if the triangle
+    /// belongs to the player to move.
+    pub fn from_edges(colors: [EdgeState; 15], first: EdgeState) -> Result<Self, SimError> {
+        if first.is_none() {
+            return Err(SimError::InvalidFirstPlayer);
+        }
+
+        let mut out = Self::new();
+        for (edge, &color) in Edge::all_edges().zip(&colors) {
+            out.set_edge(edge, color);
+        }
+
+        let second = first.opponent();
+        out.to_move = match (out.count(first), out.count(second)) {
+            (a, b) if a == b => first,
+            (a, b) if a == b + 1 => second,
+            _ => return Err(SimError::InvalidPosition),
+        };
+
+        match (out.has_triangle(EdgeState::Red), out.has_triangle(EdgeState::Blue)) {
+            (true, true) => return Err(SimError::InvalidPosition),
+            (true, false) => out.winner = Some(EdgeState::Blue),
+            (false, true) => out.winner = Some(EdgeState::Red),
+            (false, false) => {}
+        }
+
+        // The loser has to be the player who made the last move.
+        if out.winner == Some(out.to_move.opponent()) {
+            return Err(SimError::InvalidPosition);
+        }
+
+        Ok(out)
+    }
+
+    /// Returns the player who makes the next move.
+    pub fn to_move(&self) -> EdgeState {
+        self.to_move
+    }
+
+    /// Returns the winner of the game or `None` if the game is still running.
+    pub fn winner(&self) -> Option<EdgeState> {
+        self.winner
+    }
+
+    /// Returns the bitmask of all edges with the given color. Bit `i`
+    /// corresponds to the edge with ID `i`. For `EdgeState::None`, the mask
+    /// of all uncolored edges is returned.
+    pub fn mask(&self, color: EdgeState) -> u16 {
+        match color {
+            EdgeState::None => !(self.red | self.blue) & ALL_EDGES,
+            EdgeState::Red => self.red,
+            EdgeState::Blue => self.blue,
+        }
+    }
+
+    /// Returns the number of edges with the given color.
+    pub fn count(&self, color: EdgeState) -> usize {
+        self.mask(color).count_ones() as usize
+    }
+
+    /// Returns the bitmask of all edges the player to move can color. If the
+    /// game is over, there are no legal moves.
+    pub fn legal_mask(&self) -> u16 {
+        if self.winner.is_some() {
+            0
+        } else {
+            self.mask(EdgeState::None)
+        }
+    }
+
+    /// Returns the bitmask of all legal moves that don't create a triangle in
+    /// the given color.
+    pub fn safe_mask(&self, color: EdgeState) -> u16 {
+        let own = self.mask(color);
+
+        // Every triangle with two edges of `color` makes the third edge
+        // unsafe.
+        let unsafe_edges = TRIANGLE_MASKS.iter()
+            .filter(|&&t| (t & own).count_ones() == 2)
+            .fold(0, |acc, &t| acc | (t & !own));
+
+        self.legal_mask() & !unsafe_edges
+    }
+
+    /// Returns an iterator over all edges the player to move can color. If the
+    /// game is over, there are no legal moves.
+    pub fn legal_moves(&self) -> impl Iterator<Item = Edge> {
+        edges_in_mask(self.legal_mask())
+    }
+
+    /// Returns an iterator over all legal moves that don't create a triangle
+    /// in the given color.
+    pub fn safe_moves(&self, color: EdgeState) -> impl Iterator<Item = Edge> {
+        edges_in_mask(self.safe_mask(color))
+    }
+
+    /// Returns `true` if there is a triangle in the given color.
+    pub fn has_triangle(&self, color: EdgeState) -> bool {
+        let own = self.mask(color);
+        TRIANGLE_MASKS.iter().any(|&t| t & !own == 0)
+    }
+
+    /// Returns the vertices of a triangle in the given color, if there is one.
+    pub fn find_triangle(&self, color: EdgeState) -> Option<[Vertex; 3]> {
+        let own = self.mask(color);
+        for a in Vertex::all_vertices() {
+            for b in Vertex::all_vertices().filter(|b| b.id() > a.id()) {
+                for c in Vertex::all_vertices().filter(|c| c.id() > b.id()) {
+                    let triangle = Edge::between(a, b).bit()
+                        | Edge::between(a, c).bit()
+                        | Edge::between(b, c).bit();
+                    if triangle & !own == 0 {
+                        return Some([a, b, c]);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Colors `edge` in the color of the player to move and passes the turn to
+    /// the other player.
+    ///
+    /// Returns an error if the edge is already colored or the game is already
+    /// over.
+    pub fn play(&mut self, edge: Edge) -> Result<Outcome, SimError> {
+        if self.winner.is_some() {
+            return Err(SimError::GameOver);
+        }
+        if !self.edge_state(edge).is_none() {
+            return Err(SimError::EdgeOccupied(edge));
+        }
+
+        let color = self.to_move;
+        let loses = self.would_create_triangle(edge, color);
+        self.set_edge(edge, color);
+        self.to_move = color.opponent();
+
+        if loses {
+            self.winner = Some(color.opponent());
+            Ok(Outcome::Won(color.opponent()))
+        } else {
+            Ok(Outcome::Ongoing)
         }
     }
 
     /// Returns the state of the given edge.
     pub fn edge_state(&self, id: Edge) -> EdgeState {
-        // First we shift the bits to the right so that the relevant two bits
-        // are the LSBs. Then we mask of the other stuff.
-        match (self.encoded >> (id.id() * 2)) & 0b11 {
-            0 => EdgeState::None,
-            1 => EdgeState::Red,
-            2 => EdgeState::Blue,
-            _ => unreachable!(),
+        let bit = id.bit();
+        if self.red & bit != 0 {
+            EdgeState::Red
+        } else if self.blue & bit != 0 {
+            EdgeState::Blue
+        } else {
+            EdgeState::None
         }
     }
 
     /// Sets the state of the given edge to `state`.
     pub fn set_edge(&mut self, id: Edge, state: EdgeState) {
-        let bits = state as u8 as u32;
-
-        // We need to set two bits without touching any other bits. We do this
-        // by first setting the relevant bits to 0 and then set the value by
-        // or-ing. Example (id is 3, state is 2 = 0b10):
-        //
-        // original encoded:        ee dd cc bb aa
-        // mask:                 &  11 00 11 11 11
-        // shifted_bits:         |  00 10 00 00 00
-        //                     -------------------------
-        // result:                  ee 10 cc bb aa
-        let mask = !(0b11 << (id.id() * 2));
-        let shifted_bits = bits << (id.id() * 2);
-        self.encoded = (self.encoded & mask) | shifted_bits;
+        let bit = id.bit();
+        self.red &= !bit;
+        self.blue &= !bit;
+        match state {
+            EdgeState::None => {}
+            EdgeState::Red => self.red |= bit,
+            EdgeState::Blue => self.blue |= bit,
+        }
     }
 
     /// Checks whether setting `edge` to `color` would result in a triangle.
     pub fn would_create_triangle(&self, edge: Edge, color: EdgeState) -> bool {
-        let (va, vb) = edge.endpoints();
-        Vertex::all_vertices()
-            .filter(|&v| v != va && v != vb)
-            .map(|third| (Edge::between(va, third), Edge::between(vb, third)))
-            .any(|(ea, eb)| self.edge_state(ea) == color && self.edge_state(eb) == color)
+        // `EDGE_TRIANGLES` contains the other two edges of all triangles
+        // `edge` is part of. If both are colored, we get a triangle.
+        let own = self.mask(color);
+        EDGE_TRIANGLES[edge.id() as usize].iter().any(|&others| others & !own == 0)
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Mask with the bits of all 15 edges set.
+const ALL_EDGES: u16 = (1 << 15) - 1;
+
+/// The bitmasks of all 20 triangles (each with the bits of its three edges
+/// set).
+pub const TRIANGLE_MASKS: [u16; 20] = triangle_masks();
+
+/// For each edge, the four triangles it is part of. Each entry is the mask of
+/// the *other* two edges of the triangle.
+const EDGE_TRIANGLES: [[u16; 4]; 15] = edge_triangles();
+
+/// Returns the ID of the edge between the vertices `a` and `b` (`a < b`).
+/// Same as `Edge::between`, but usable in constant expressions.
+const fn edge_id(a: u8, b: u8) -> u8 {
+    a * (11 - a) / 2 + (b - a - 1)
+}
+
+const fn triangle_masks() -> [u16; 20] {
+    let mut out = [0; 20];
+    let mut i = 0;
+    let mut a = 0;
+    while a < 6 {
+        let mut b = a + 1;
+        while b < 6 {
+            let mut c = b + 1;
+            while c < 6 {
+                out[i] = (1 << edge_id(a, b)) | (1 << edge_id(a, c)) | (1 << edge_id(b, c));
+                i += 1;
+                c += 1;
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+    out
+}
+
+const fn edge_triangles() -> [[u16; 4]; 15] {
+    let triangles = triangle_masks();
+    let mut out = [[0; 4]; 15];
+    let mut edge = 0;
+    while edge < 15 {
+        let bit = 1 << edge;
+        let mut n = 0;
+        let mut i = 0;
+        while i < 20 {
+            if triangles[i] & bit != 0 {
+                out[edge][n] = triangles[i] & !bit;
+                n += 1;
+            }
+            i += 1;
+        }
+        edge += 1;
+    }
+    out
+}
+
+/// Returns an iterator over all edges whose bits are set in `mask`.
+fn edges_in_mask(mask: u16) -> impl Iterator<Item = Edge> {
+    Edge::all_edges().filter(move |e| mask & e.bit() != 0)
+}
+
+/// The result of a single move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The game continues.
+    Ongoing,
+
+    /// The move created a triangle and thus the given player won.
+    Won(EdgeState),
+}
+
+/// Errors that can occur when creating game objects from untrusted data or
+/// when making moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimError {
+    /// An edge ID that is not less than 15.
+    InvalidEdgeId(u8),
+
+    /// A vertex ID that is not less than 6.
+    InvalidVertexId(u8),
+
+    /// An edge from a vertex to itself was requested.
+    SelfLoop(Vertex),
+
+    /// The edge is already colored.
+    EdgeOccupied(Edge),
+
+    /// The game is already over, so no more moves can be made.
+    GameOver,
+
+    /// The numbers of red and blue edges (or the triangles) in a position are
+    /// inconsistent.
+    InvalidPosition,
+
+    /// `EdgeState::None` was given as the player who made the first move.
+    InvalidFirstPlayer,
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimError::InvalidEdgeId(id) => write!(f, "invalid edge ID {} (must be < 15)", id),
+            SimError::InvalidVertexId(id) => write!(f, "invalid vertex ID {} (must be < 6)", id),
+            SimError::SelfLoop(v) => write!(f, "there is no edge from vertex {} to itself", v.id()),
+            SimError::EdgeOccupied(edge) => write!(f, "edge {} is already colored", edge),
+            SimError::GameOver => write!(f, "the game is already over"),
+            SimError::InvalidPosition => {
+                write!(f, "numbers of red and blue edges or triangles don't match")
+            }
+            SimError::InvalidFirstPlayer => write!(f, "the first player has to be red or blue"),
+        }
     }
 }
 
+impl error::Error for SimError {}
+
 /// Represents an edge. It can either be uncolored (`None`) or be colored by
 /// one player.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum EdgeState {
     None = 0,
@@ -81,6 +367,15 @@ impl EdgeState {
     pub fn is_none(&self) -> bool {
         *self == EdgeState::None
     }
+
+    /// Returns the color of the other player. `None` stays `None`.
+    pub fn opponent(&self) -> Self {
+        match self {
+            EdgeState::None => EdgeState::None,
+            EdgeState::Red => EdgeState::Blue,
+            EdgeState::Blue => EdgeState::Red,
+        }
+    }
 }
 
 /// An edge, represented by an id from 0 to 14 inclusive.
@@ -89,41 +384,67 @@ pub struct Edge(u8);
 
 impl Edge {
     /// Creates a new ID from the given integer. `v` has to be less than 15!
+    /// Use `try_new` for untrusted input.
     pub fn new(v: u8) -> Self {
         assert!(v < 15);
         Self(v)
     }
 
+    /// Creates a new ID from the given integer or returns an error if `v` is
+    /// not less than 15.
+    pub fn try_new(v: u8) -> Result<Self, SimError> {
+        if v < 15 {
+            Ok(Self(v))
+        } else {
+            Err(SimError::InvalidEdgeId(v))
+        }
+    }
+
     /// Returns the inner ID.
     pub fn id(&self) -> u8 {
         self.0
     }
 
+    /// Returns the bit representing this edge in bitmasks (see
+    /// `GameState::mask`).
+    pub fn bit(&self) -> u16 {
+        1 << self.0
+    }
+
     /// Returns an iterator over all edges.
     pub fn all_edges() -> impl Iterator<Item = Self> {
         (0..15).map(Self::new)
     }
 
-    /// Returns the edge between the two given vertices.
+    /// Returns the edge between the two given vertices. `a` and `b` have to
+    /// be different! Use `try_between` for untrusted input.
     pub fn between(a: Vertex, b: Vertex) -> Self {
-        match (a.id(), b.id()) {
-            (0, 1) | (1, 0) => Self::new(0),
-            (0, 2) | (2, 0) => Self::new(1),
-            (0, 3) | (3, 0) => Self::new(2),
-            (0, 4) | (4, 0) => Self::new(3),
-            (0, 5) | (5, 0) => Self::new(4),
-            (1, 2) | (2, 1) => Self::new(5),
-            (1, 3) | (3, 1) => Self::new(6),
-            (1, 4) | (4, 1) => Self::new(7),
-            (1, 5) | (5, 1) => Self::new(8),
-            (2, 3) | (3, 2) => Self::new(9),
-            (2, 4) | (4, 2) => Self::new(10),
-            (2, 5) | (5, 2) => Self::new(11),
-            (3, 4) | (4, 3) => Self::new(12),
-            (3, 5) | (5, 3) => Self::new(13),
-            (4, 5) | (5, 4) => Self::new(14),
-            _ => unreachable!(),
-        }
+        Self::try_between(a, b).expect("there is no edge from a vertex to itself")
+    }
+
+    /// Returns the edge between the two given vertices or an error if both
+    /// vertices are the same.
+    pub fn try_between(a: Vertex, b: Vertex) -> Result<Self, SimError> {
+        let id = match (a.id(), b.id()) {
+            (0, 1) | (1, 0) => 0,
+            (0, 2) | (2, 0) => 1,
+            (0, 3) | (3, 0) => 2,
+            (0, 4) | (4, 0) => 3,
+            (0, 5) | (5, 0) => 4,
+            (1, 2) | (2, 1) => 5,
+            (1, 3) | (3, 1) => 6,
+            (1, 4) | (4, 1) => 7,
+            (1, 5) | (5, 1) => 8,
+            (2, 3) | (3, 2) => 9,
+            (2, 4) | (4, 2) => 10,
+            (2, 5) | (5, 2) => 11,
+            (3, 4) | (4, 3) => 12,
+            (3, 5) | (5, 3) => 13,
+            (4, 5) | (5, 4) => 14,
+            _ => return Err(SimError::SelfLoop(a)),
+        };
+
+        Ok(Self(id))
     }
 
     /// Returns the IDs of the two endpoints of this edge.
@@ -149,18 +470,44 @@ impl Edge {
     }
 }
 
+/// Formats the edge by its two endpoints, e.g. `0-3`.
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (a, b) = self.endpoints();
+        write!(f, "{}-{}", a.id(), b.id())
+    }
+}
+
+impl TryFrom<u8> for Edge {
+    type Error = SimError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        Self::try_new(v)
+    }
+}
+
 
 /// A vertex, represented by an ID from 0 to 5 inclusive.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Vertex(u8);
 
 impl Vertex {
-    /// Creates a new ID. `v` has to be less than 6!
+    /// Creates a new ID. `v` has to be less than 6! Use `try_new` for
+    /// untrusted input.
     pub fn new(v: u8) -> Self {
         assert!(v < 6);
         Self(v)
     }
 
+    /// Creates a new ID or returns an error if `v` is not less than 6.
+    pub fn try_new(v: u8) -> Result<Self, SimError> {
+        if v < 6 {
+            Ok(Self(v))
+        } else {
+            Err(SimError::InvalidVertexId(v))
+        }
+    }
+
     /// Returns the inner ID.
     pub fn id(&self) -> u8 {
         self.0
@@ -170,3 +517,178 @@ impl Vertex {
         (0..6).map(Self::new)
     }
 }
+
+impl TryFrom<u8> for Vertex {
+    type Error = SimError;
+
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        Self::try_new(v)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::player::seeded_rng;
+    use super::*;
+
+    /// Returns the colors of all edges for a position with the given red and
+    /// blue edge IDs.
+    fn colors(red: &[u8], blue: &[u8]) -> [EdgeState; 15] {
+        let mut out = [EdgeState::None; 15];
+        for &id in red {
+            out[id as usize] = EdgeState::Red;
+        }
+        for &id in blue {
+            out[id as usize] = EdgeState::Blue;
+        }
+        out
+    }
+
+    #[test]
+    fn play_occupied_edge() {
+        let mut state = GameState::new();
+        assert_eq!(state.play(Edge::new(0)), Ok(Outcome::Ongoing));
+        assert_eq!(state.play(Edge::new(0)), Err(SimError::EdgeOccupied(Edge::new(0))));
+        assert_eq!(state.to_move(), EdgeState::Blue);
+    }
+
+    #[test]
+    fn play_after_game_over() {
+        // Red completes the triangle 0-1-2 (edges 0, 1 and 5).
+        let mut state = GameState::new();
+        for &id in &[0, 2, 1, 3] {
+            assert_eq!(state.play(Edge::new(id)), Ok(Outcome::Ongoing));
+        }
+        assert_eq!(state.play(Edge::new(5)), Ok(Outcome::Won(EdgeState::Blue)));
+        assert_eq!(state.winner(), Some(EdgeState::Blue));
+        assert_eq!(state.play(Edge::new(4)), Err(SimError::GameOver));
+    }
+
+    #[test]
+    fn from_edges_to_move() {
+        let state = GameState::from_edges(colors(&[0, 1], &[2]), EdgeState::Red).unwrap();
+        assert_eq!(state.to_move(), EdgeState::Blue);
+        let state = GameState::from_edges(colors(&[0], &[1, 2]), EdgeState::Blue).unwrap();
+        assert_eq!(state.to_move(), EdgeState::Red);
+        let state = GameState::from_edges(colors(&[], &[]), EdgeState::Blue).unwrap();
+        assert_eq!(state.to_move(), EdgeState::Blue);
+    }
+
+    #[test]
+    fn from_edges_invalid_counts() {
+        for &(red, blue) in &[(&[0, 1, 2][..], &[][..]), (&[], &[0]), (&[0], &[1, 2, 3])] {
+            assert_eq!(
+                GameState::from_edges(colors(red, blue), EdgeState::Red).err(),
+                Some(SimError::InvalidPosition),
+            );
+        }
+    }
+
+    #[test]
+    fn from_edges_triangles() {
+        // Red completed the triangle 0-1-2 with the last move.
+        let state = GameState::from_edges(colors(&[0, 1, 5], &[2, 3]), EdgeState::Red).unwrap();
+        assert_eq!(state.winner(), Some(EdgeState::Blue));
+        assert_eq!(state.legal_mask(), 0);
+
+        // Triangles of both colors (the second one is 3-4-5).
+        assert_eq!(
+            GameState::from_edges(colors(&[0, 1, 5], &[12, 13, 14]), EdgeState::Red).err(),
+            Some(SimError::InvalidPosition),
+        );
+
+        // Red has a triangle, but blue made the last move.
+        assert_eq!(
+            GameState::from_edges(colors(&[0, 1, 5], &[2, 3, 4]), EdgeState::Red).err(),
+            Some(SimError::InvalidPosition),
+        );
+    }
+
+    #[test]
+    fn from_edges_without_first_player() {
+        assert_eq!(
+            GameState::from_edges(colors(&[], &[]), EdgeState::None).err(),
+            Some(SimError::InvalidFirstPlayer),
+        );
+    }
+
+    #[test]
+    fn edge_ids() {
+        let mut ids = vec![];
+        for a in 0..6 {
+            for b in a + 1..6 {
+                let edge = Edge::between(Vertex::new(a), Vertex::new(b));
+                assert_eq!(edge.id(), edge_id(a, b));
+                assert_eq!(edge, Edge::between(Vertex::new(b), Vertex::new(a)));
+                assert_eq!(edge.endpoints(), (Vertex::new(a), Vertex::new(b)));
+                ids.push(edge.id());
+            }
+        }
+        assert_eq!(ids, (0..15).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn triangles_per_edge() {
+        for edge in Edge::all_edges() {
+            let triangles = TRIANGLE_MASKS.iter().filter(|&&t| t & edge.bit() != 0).count();
+            assert_eq!(triangles, 4);
+
+            let others = &EDGE_TRIANGLES[edge.id() as usize];
+            for (i, &mask) in others.iter().enumerate() {
+                assert_eq!(mask.count_ones(), 2);
+                assert_eq!(mask & edge.bit(), 0);
+                assert!(TRIANGLE_MASKS.contains(&(mask | edge.bit())));
+                assert!(!others[..i].contains(&mask));
+            }
+        }
+    }
+
+    /// Counts the triangles of the given color vertex by vertex, without
+    /// using the precomputed masks.
+    fn brute_force_triangles(state: &GameState, color: EdgeState) -> usize {
+        let has_color = |a, b| state.edge_state(Edge::between(Vertex(a), Vertex(b))) == color;
+        let mut count = 0;
+        for a in 0..6 {
+            for b in a + 1..6 {
+                for c in b + 1..6 {
+                    if has_color(a, b) && has_color(a, c) && has_color(b, c) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn triangle_masks_match_brute_force() {
+        let mut rng = seeded_rng(31);
+        for _ in 0..10_000 {
+            let mut state = GameState::new();
+            for edge in Edge::all_edges() {
+                let colors = [EdgeState::None, EdgeState::Red, EdgeState::Blue];
+                state.set_edge(edge, colors[rng.gen_range(0, 3)]);
+            }
+
+            for &color in &[EdgeState::Red, EdgeState::Blue] {
+                let triangles = brute_force_triangles(&state, color);
+                assert_eq!(state.has_triangle(color), triangles > 0);
+                assert_eq!(state.find_triangle(color).is_some(), state.has_triangle(color));
+
+                let mut safe = 0;
+                for edge in Edge::all_edges().filter(|e| state.edge_state(*e).is_none()) {
+                    let mut next = state.clone();
+                    next.set_edge(edge, color);
+                    let creates = brute_force_triangles(&next, color) > triangles;
+                    assert_eq!(state.would_create_triangle(edge, color), creates);
+                    if !creates {
+                        safe |= edge.bit();
+                    }
+                }
+                assert_eq!(state.safe_mask(color), safe);
+            }
+        }
+    }
+}
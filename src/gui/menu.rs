@@ -0,0 +1,300 @@
+//! The setup menu shown before a game (unless disabled with
+//! `GuiOptions::menu`).
+
+use quicksilver::{
+    Error,
+    geom::{Rectangle, Shape, Vector},
+    graphics::{Background, Font, FontStyle, Image},
+    input::{Key, MouseButton},
+    lifecycle::Window,
+};
+
+use sim::{player::PlayerConfig, theme::Theme};
+
+use super::{Layout, Safety, color};
+
+
+/// Players offered in addition to the ones given on the command line.
+const PRESETS: [&str; 6] = [
+    "human",
+    "random",
+    "dumb_random",
+    "minimax",
+    "minimax:randomize=false",
+    "heuristic",
+];
+
+/// The font size of the menu (scaled like all sizes).
+const MENU_TEXT_SIZE: f32 = 48.0;
+
+/// The lines of the menu that react to clicks.
+const RED_LINE: usize = 2;
+const RED_OPTIONS_LINE: usize = 3;
+const BLUE_LINE: usize = 4;
+const BLUE_OPTIONS_LINE: usize = 5;
+const SWAP_LINE: usize = 6;
+const SAFETY_LINE: usize = 7;
+const START_LINE: usize = 8;
+
+/// The safety modes in the order they are cycled through.
+const SAFETY_MODES: [Safety; 3] = [Safety::Off, Safety::Warn, Safety::Forbid];
+
+/// Lets the user choose the players and the safety mode of the next game.
+///
+/// Red always makes the first move, so the starting player is chosen by
+/// swapping the players. The options of a player are edited as text in the
+/// same format as on the command line (e.g. `randomize=false,seed=3`). The
+/// game has no rule variants to choose from.
+pub(super) struct Menu {
+    /// All players that can be chosen. Players with edited options are
+    /// added.
+    choices: Vec<PlayerConfig>,
+
+    /// Indices into `choices`.
+    red: usize,
+    blue: usize,
+
+    /// Index into `SAFETY_MODES`.
+    safety: usize,
+
+    /// The options line being edited and the text typed so far.
+    editing: Option<(usize, String)>,
+
+    /// The error of the last edit, shown instead of the instructions.
+    error: Option<String>,
+
+    /// The rendered lines of the menu.
+    texts: Vec<Image>,
+
+    theme: Theme,
+}
+
+impl Menu {
+    /// Creates a menu with `red`, `blue` and `safety` preselected.
+    pub(super) fn new(
+        red: &PlayerConfig,
+        blue: &PlayerConfig,
+        safety: Safety,
+        font: &Font,
+        theme: Theme,
+    ) -> Self {
+        let mut choices = PRESETS.iter()
+            .map(|s| s.parse().expect("bug: invalid preset player"))
+            .collect::<Vec<PlayerConfig>>();
+        for config in &[red, blue] {
+            if !choices.contains(config) {
+                choices.push((*config).clone());
+            }
+        }
+
+        let index = |config| choices.iter().position(|c| c == config).unwrap();
+        let mut out = Self {
+            red: index(red),
+            blue: index(blue),
+            safety: SAFETY_MODES.iter().position(|&s| s == safety).unwrap(),
+            editing: None,
+            error: None,
+            choices,
+            texts: vec![],
+            theme,
+        };
+        out.update_texts(font);
+        out
+    }
+
+    /// Returns the chosen red and blue player.
+    pub(super) fn players(&self) -> (PlayerConfig, PlayerConfig) {
+        (self.choices[self.red].clone(), self.choices[self.blue].clone())
+    }
+
+    /// Returns the chosen safety mode.
+    pub(super) fn safety(&self) -> Safety {
+        SAFETY_MODES[self.safety]
+    }
+
+    /// Returns `true` while the options of a player are edited. All key
+    /// presses should then be passed to `edit_key`.
+    pub(super) fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    /// Handles a click at `p`. Clicking a player or the safety mode selects
+    /// the next one (with the left button) or the previous one (with any
+    /// other button). Clicking the options of a player starts editing them;
+    /// clicking anywhere else discards an unfinished edit.
+    /// Returns `true` if the game should be started.
+    pub(super) fn click(
+        &mut self,
+        p: Vector,
+        button: MouseButton,
+        layout: &Layout,
+        font: &Font,
+    ) -> bool {
+        let line = match (0..self.texts.len()).find(|&i| layout.menu_line(i).contains(p)) {
+            Some(line) => line,
+            None => return false,
+        };
+        if self.editing.as_ref().map(|&(editing, _)| editing) == Some(line) {
+            return false;
+        }
+        self.editing = None;
+        self.error = None;
+
+        let cycle = |i: &mut usize, n: usize| {
+            *i = if button == MouseButton::Left { (*i + 1) % n } else { (*i + n - 1) % n };
+        };
+        match line {
+            RED_LINE => cycle(&mut self.red, self.choices.len()),
+            BLUE_LINE => cycle(&mut self.blue, self.choices.len()),
+            RED_OPTIONS_LINE | BLUE_OPTIONS_LINE => {
+                let options = self.player(line).to_string()
+                    .split_once(':')
+                    .map(|(_, options)| options.to_string())
+                    .unwrap_or_default();
+                self.editing = Some((line, options));
+            }
+            SWAP_LINE => std::mem::swap(&mut self.red, &mut self.blue),
+            SAFETY_LINE => cycle(&mut self.safety, SAFETY_MODES.len()),
+            START_LINE => return true,
+            _ => return false,
+        }
+
+        self.update_texts(font);
+        false
+    }
+
+    /// Handles a key press while editing: Enter applies the options,
+    /// Escape discards them and Backspace deletes the last character.
+    pub(super) fn edit_key(&mut self, key: Key, font: &Font) {
+        match key {
+            Key::Return | Key::NumpadEnter => self.apply_edit(),
+            Key::Escape => self.editing = None,
+            Key::Back => {
+                if let Some((_, text)) = &mut self.editing {
+                    text.pop();
+                }
+            }
+            _ => return,
+        }
+        self.update_texts(font);
+    }
+
+    /// Adds a typed character to the options being edited.
+    pub(super) fn type_char(&mut self, c: char, font: &Font) {
+        if let Some((_, text)) = &mut self.editing {
+            text.push(c);
+            self.update_texts(font);
+        }
+    }
+
+    /// Replaces the player whose options are edited by the same player with
+    /// the typed options. If the options are invalid, the error is shown and
+    /// editing continues.
+    fn apply_edit(&mut self) {
+        let (line, text) = match &self.editing {
+            Some(editing) => editing,
+            None => return,
+        };
+
+        let name = self.player(*line).name();
+        let spec = if text.trim().is_empty() {
+            name.to_string()
+        } else {
+            format!("{}:{}", name, text)
+        };
+        match spec.parse::<PlayerConfig>() {
+            Ok(config) => {
+                let index = match self.choices.iter().position(|c| *c == config) {
+                    Some(index) => index,
+                    None => {
+                        self.choices.push(config);
+                        self.choices.len() - 1
+                    }
+                };
+                if *line == RED_OPTIONS_LINE {
+                    self.red = index;
+                } else {
+                    self.blue = index;
+                }
+                self.editing = None;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Returns the player the given player or options line belongs to.
+    fn player(&self, line: usize) -> &PlayerConfig {
+        match line {
+            RED_LINE | RED_OPTIONS_LINE => &self.choices[self.red],
+            _ => &self.choices[self.blue],
+        }
+    }
+
+    fn update_texts(&mut self, font: &Font) {
+        let (red, blue) = self.players();
+        let theme = &self.theme;
+        let options = |line: usize| match &self.editing {
+            Some((editing, text)) if *editing == line => format!("  Options: {}_", text),
+            _ => match self.player(line).to_string().split_once(':') {
+                Some((_, options)) => format!("  Options: {}", options),
+                None => "  Options: none (click to edit)".to_string(),
+            },
+        };
+        let help = match (&self.error, &self.editing) {
+            (Some(error), _) => (error.clone(), theme.warning),
+            (None, Some(_)) => (
+                "Type the options, e.g. seed=3 (Enter: apply, Escape: cancel)".to_string(),
+                theme.point,
+            ),
+            (None, None) => (
+                "Click a player or its options to change them (right click: back)".to_string(),
+                theme.point,
+            ),
+        };
+        let lines = [
+            ("New game".to_string(), theme.point),
+            help,
+            (format!("Red (moves first): {}", red.name()), theme.red),
+            (options(RED_OPTIONS_LINE), theme.red),
+            (format!("Blue: {}", blue.name()), theme.blue),
+            (options(BLUE_OPTIONS_LINE), theme.blue),
+            ("Swap players".to_string(), theme.point),
+            (format!("Losing moves of humans: {}", self.safety()), theme.point),
+            ("Start game (Enter)".to_string(), theme.point),
+        ];
+
+        self.texts = lines.iter()
+            .map(|(text, rgb)| {
+                font.render(text, &FontStyle::new(MENU_TEXT_SIZE, color(*rgb)))
+                    .expect("failed to render text")
+            })
+            .collect();
+    }
+
+    /// Draws the menu. The clickable line under the mouse cursor is
+    /// highlighted.
+    pub(super) fn draw(
+        &self,
+        window: &mut Window,
+        layout: &Layout,
+        mouse_pos: Vector,
+    ) -> Result<(), Error> {
+        for (i, text) in self.texts.iter().enumerate() {
+            let area = layout.menu_line(i);
+            if i >= RED_LINE && area.contains(mouse_pos) {
+                window.draw(&area, Background::Col(color(self.theme.selected).with_alpha(0.3)));
+            }
+
+            // Texts too wide for the line are shrunk.
+            let size = text.area().size() * layout.scale;
+            let size = size * (area.width() / size.x).min(1.0);
+            window.draw(
+                &Rectangle::new_sized(size).with_center(area.center()),
+                Background::Img(text),
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1190 @@
+//! A GUI for playing the SIM game.
+
+use std::{
+    fmt,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use quicksilver::{
+    Error,
+    combinators::Future,
+    geom::{Circle, Line, Rectangle, Vector, Shape},
+    graphics::{Background, Color, Image, Font, FontStyle, View},
+    input::{ButtonState, MouseButton, MouseCursor, Key},
+    lifecycle::{Event, State, Window},
+};
+
+
+use rand::{Rng, thread_rng};
+
+use sim::{
+    analysis::print_analysis,
+    clock::{Clock, TimeControl},
+    player::{Player, PlayerConfig, derive_seeds},
+    game::{GameState, Edge, EdgeState, Outcome, Vertex},
+    notation::{GameRecord, play_moves},
+    render::{self, Geometry},
+    runner::Forfeit,
+    theme::Theme,
+};
+
+use self::menu::Menu;
+
+mod menu;
+
+
+/// If the mouse cursor is closer to a line than this distance (scaled like
+/// all sizes, see `render::Geometry`), we say the mouse hovers over the line.
+const HOVER_DISTANCE: f32 = 12.0;
+
+/// The vertical center of the text at the top of the window.
+const TEXT_CENTER_Y: f32 = 50.0;
+
+/// The fraction of the window width used by the side panel.
+const PANEL_FRACTION: f32 = 0.3;
+
+/// Font size, line height, inner padding and the top of the first line of
+/// the side panel (scaled like all sizes).
+const PANEL_TEXT_SIZE: f32 = 32.0;
+const PANEL_LINE_HEIGHT: f32 = 40.0;
+const PANEL_PADDING: f32 = 20.0;
+const PANEL_TOP: f32 = 50.0;
+
+/// The panel line with the clocks of both players (in games with a time
+/// control) and the line of the first move. The lines in between show the
+/// game status.
+const CLOCK_LINE: usize = 2;
+const FIRST_MOVE_LINE: usize = 5;
+
+/// In `InputMode::Drag`, a drag starts or ends at a vertex if the mouse
+/// cursor is closer to it than this distance (scaled like all sizes).
+const GRAB_DISTANCE: f32 = 30.0;
+
+/// The distance of the vertex labels from the vertex centers (scaled like all
+/// sizes).
+const LABEL_DISTANCE: f32 = 28.0;
+
+/// The top of the first line and the line height of the setup menu (scaled
+/// like all sizes).
+const MENU_TOP: f32 = 150.0;
+const MENU_LINE_HEIGHT: f32 = 80.0;
+
+/// Returns the window size for which the board is `board_size` pixels wide
+/// and high.
+pub(crate) fn window_size(board_size: u32) -> Vector {
+    let board_size = board_size as f32;
+    Vector::new(board_size / (1.0 - PANEL_FRACTION), board_size)
+}
+
+/// Converts a color of a `Theme` (shared with the SVG/PNG renderer).
+fn color([r, g, b]: render::Rgb) -> Color {
+    Color { r, g, b, a: 1.0 }
+}
+
+
+/// Positions and sizes of everything on screen, computed from the window
+/// size. The board fills the left part of the window, the side panel the
+/// right part.
+#[derive(Clone, Debug)]
+struct Layout {
+    /// The window size this layout was computed for.
+    size: Vector,
+
+    /// The area of the side panel.
+    panel: Rectangle,
+
+    /// Factor by which all sizes are scaled relative to a window of size
+    /// `render::REFERENCE_SIZE`.
+    scale: f32,
+
+    /// The positions of all vertices, indexed by vertex ID.
+    corners: Vec<Vector>,
+
+    /// The center of the board.
+    center: Vector,
+}
+
+impl Layout {
+    fn new(size: Vector) -> Self {
+        let board_width = size.x * (1.0 - PANEL_FRACTION);
+        let geometry = Geometry::new(board_width, size.y, render::NUM_VERTICES);
+        Self {
+            size,
+            panel: Rectangle::new((board_width, 0.0), (size.x - board_width, size.y)),
+            scale: geometry.scale,
+            corners: geometry.corners.into_iter().map(Vector::from).collect(),
+            center: Vector::new(board_width / 2.0, size.y / 2.0),
+        }
+    }
+
+    /// Returns the center of the label of `v`, which is placed outside of the
+    /// board, to the left or right of the vertex (so that the labels of the
+    /// top vertices don't collide with the text at the top).
+    fn label_center(&self, v: Vertex) -> Vector {
+        let corner = self.corner(v);
+        let direction = (corner.x - self.center.x).signum();
+        corner + Vector::new(direction * LABEL_DISTANCE * self.scale, 0.0)
+    }
+
+    fn corner(&self, v: Vertex) -> Vector {
+        self.corners[v.id() as usize]
+    }
+
+    /// Returns the endpoints of `e` on screen.
+    fn line(&self, e: Edge) -> (Vector, Vector) {
+        let (va, vb) = e.endpoints();
+        (self.corner(va), self.corner(vb))
+    }
+
+    /// Returns the uncolored edge the point `p` hovers over, if any.
+    fn edge_at(&self, state: &GameState, p: Vector) -> Option<Edge> {
+        Edge::all_edges()
+            .filter(|e| state.edge_state(*e).is_none())
+            .find(|e| distance_to_point(self.line(*e), p) < HOVER_DISTANCE * self.scale)
+    }
+
+    /// Returns the vertex close to the point `p`, if any.
+    fn vertex_at(&self, p: Vector) -> Option<Vertex> {
+        Vertex::all_vertices().find(|&v| self.corner(v).distance(p) < GRAB_DISTANCE * self.scale)
+    }
+
+    /// Returns the area of the `i`-th line of the side panel.
+    fn panel_line(&self, i: usize) -> Rectangle {
+        let padding = PANEL_PADDING * self.scale;
+        let y = (PANEL_TOP + i as f32 * PANEL_LINE_HEIGHT) * self.scale;
+        Rectangle::new(
+            (self.panel.x() + padding, y),
+            (self.panel.width() - 2.0 * padding, PANEL_LINE_HEIGHT * self.scale),
+        )
+    }
+
+    /// Returns the area of the `i`-th line of the setup menu, which is
+    /// centered in the whole window.
+    fn menu_line(&self, i: usize) -> Rectangle {
+        let y = (MENU_TOP + i as f32 * MENU_LINE_HEIGHT) * self.scale;
+        Rectangle::new(
+            (self.size.x * 0.1, y),
+            (self.size.x * 0.8, MENU_LINE_HEIGHT * self.scale),
+        )
+    }
+
+    /// Returns the index of the move (out of `num_moves`) whose line in the
+    /// side panel contains `p`, if any.
+    fn move_at(&self, p: Vector, num_moves: usize) -> Option<usize> {
+        (0..num_moves).find(|&i| self.panel_line(FIRST_MOVE_LINE + i).contains(p))
+    }
+}
+
+
+/// How the GUI treats moves of human players that would immediately lose the
+/// game (i.e. complete a triangle in their own color).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Safety {
+    /// Losing moves are treated like any other move.
+    Off,
+    /// Losing moves are highlighted and need to be clicked twice.
+    Warn,
+    /// Losing moves are highlighted and cannot be selected at all.
+    Forbid,
+}
+
+impl fmt::Display for Safety {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Safety::Off => "off",
+            Safety::Warn => "warn",
+            Safety::Forbid => "forbid",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Safety {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "off" => Ok(Safety::Off),
+            "warn" => Ok(Safety::Warn),
+            "forbid" => Ok(Safety::Forbid),
+            _ => Err(format!(
+                "invalid safety mode '{}' (valid options: 'off', 'warn', 'forbid')",
+                input,
+            )),
+        }
+    }
+}
+
+/// The time a finished game stays on screen before a new one is started with
+/// `GuiOptions::auto_restart`.
+const RESTART_DELAY: Duration = Duration::from_secs(3);
+
+/// How human players select edges with the mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InputMode {
+    /// Clicking close to an edge selects it.
+    Click,
+    /// Pressing the mouse button on a vertex and releasing it on another
+    /// vertex selects the edge between them.
+    Drag,
+}
+
+impl FromStr for InputMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "click" => Ok(InputMode::Click),
+            "drag" => Ok(InputMode::Drag),
+            _ => Err(format!(
+                "invalid input mode '{}' (valid options: 'click', 'drag')",
+                input,
+            )),
+        }
+    }
+}
+
+/// Settings of the GUI that are not about the players.
+#[derive(Clone, Debug)]
+pub(crate) struct GuiOptions {
+    /// How losing moves of human players are treated.
+    pub(crate) safety: Safety,
+
+    /// How human players select edges with the mouse.
+    pub(crate) input_mode: InputMode,
+
+    /// The colors and dash patterns of the board and texts.
+    pub(crate) theme: Theme,
+
+    /// The time control of all games (if any).
+    pub(crate) time_control: Option<TimeControl>,
+
+    /// The game is saved to this file when it ends.
+    pub(crate) record_path: Option<PathBuf>,
+
+    /// The minimum time between the previous move and a move of a computer
+    /// player.
+    pub(crate) move_delay: Duration,
+
+    /// Whether a new game is started automatically when a game ends.
+    pub(crate) auto_restart: bool,
+
+    /// Whether the setup menu is shown before the first game and when a
+    /// game ended (unless `auto_restart` is set). Otherwise the game starts
+    /// right away.
+    pub(crate) menu: bool,
+}
+
+/// A `quicksilver` state which controls the full game (polling players for
+/// moves or getting user input).
+pub(crate) struct GuiGame {
+    state: GameState,
+    player_red: Option<Box<dyn Player>>,
+    player_blue: Option<Box<dyn Player>>,
+    options: GuiOptions,
+
+    /// The red and blue player of the current (or next) game.
+    configs: (PlayerConfig, PlayerConfig),
+
+    /// The seed of the next game. A random seed is used if this is `None`.
+    next_seed: Option<u64>,
+
+    /// The setup menu, while it's shown.
+    menu: Option<Menu>,
+
+    /// The highlighted edge (if any): the edge under the mouse cursor or the
+    /// one selected with the keyboard.
+    hovered_edge: Option<Edge>,
+
+    /// The first vertex typed when selecting an edge with the keyboard.
+    typed_vertex: Option<Vertex>,
+
+    /// The vertex where the current drag started in `InputMode::Drag`.
+    drag_start: Option<Vertex>,
+
+    /// A losing edge that was clicked once in `Safety::Warn` mode. Clicking it
+    /// a second time confirms the move.
+    armed_edge: Option<Edge>,
+
+    /// A message for the human player shown in the side panel (e.g. the
+    /// warning about a losing move). Cleared when a move is made.
+    notice: Option<String>,
+
+    /// The board geometry for the current window size.
+    layout: Layout,
+
+    /// The game so far. Saved to `options.record_path` when the game ends.
+    record: GameRecord,
+
+    /// The time of the last move (or of the start of the game).
+    last_move_time: Instant,
+
+    /// The clocks of both players in games with a time control. The clock of
+    /// the active player runs since `turn_start`. For computer players, it
+    /// only runs while they search for a move (not during the move delay).
+    clock: Option<Clock>,
+    turn_start: Option<Instant>,
+
+    /// The player who lost without completing a triangle and why, if any.
+    forfeit: Option<(EdgeState, Forfeit)>,
+
+    /// While paused, computer players only move when a single step is
+    /// requested by setting `step`.
+    paused: bool,
+    step: bool,
+
+    /// The number of moves and the position shown instead of the current
+    /// position while an earlier point of the game is viewed (by clicking a
+    /// move in the side panel). No moves are made in the meantime.
+    history_view: Option<(usize, GameState)>,
+
+    /// When the running clock was stopped because an earlier position is
+    /// viewed.
+    clock_stopped: Option<Instant>,
+
+    /// The last mouse position reported by `Event::MouseMoved`.
+    mouse_pos: Vector,
+
+    font: Font,
+    player_red_text: Image,
+    player_blue_text: Image,
+
+    /// The labels of all vertices, indexed by vertex ID.
+    vertex_texts: Vec<Image>,
+
+    /// The rendered lines of the side panel with their line index. Updated
+    /// by `update_panel`.
+    panel_texts: Vec<(usize, Image)>,
+
+    /// The rendered clocks of red and blue with their text, re-rendered only
+    /// when the text changes.
+    clock_texts: Vec<(String, Image)>,
+}
+
+impl GuiGame {
+    /// Creates the GUI for games between `red` and `blue`. The first game
+    /// uses `seed` (if given).
+    pub(crate) fn new(
+        red: PlayerConfig,
+        blue: PlayerConfig,
+        seed: Option<u64>,
+        options: GuiOptions,
+    ) -> Self {
+        // Prepare text
+        let theme = options.theme;
+        let font = Font::load("FiraSans-Light.ttf").wait().expect("failed to load font");
+        let player_red_text = font
+            .render("Player Red's turn", &FontStyle::new(64.0, color(theme.red)))
+            .expect("failed to render text");
+        let player_blue_text = font
+            .render("Player Blue's turn", &FontStyle::new(64.0, color(theme.blue)))
+            .expect("failed to render text");
+        let vertex_texts = Vertex::all_vertices()
+            .map(|v| {
+                let style = FontStyle::new(PANEL_TEXT_SIZE, color(theme.point));
+                font.render(&v.id().to_string(), &style).expect("failed to render text")
+            })
+            .collect();
+
+        let mut out = Self {
+            state: GameState::new(),
+            player_red: None,
+            player_blue: None,
+            options,
+            configs: (red, blue),
+            next_seed: seed,
+            menu: None,
+
+            hovered_edge: None,
+            typed_vertex: None,
+            drag_start: None,
+            armed_edge: None,
+            notice: None,
+            layout: Layout::new(Vector::new(render::REFERENCE_SIZE, render::REFERENCE_SIZE)),
+            record: GameRecord::default(),
+            last_move_time: Instant::now(),
+            clock: None,
+            turn_start: None,
+            forfeit: None,
+            paused: false,
+            step: false,
+            history_view: None,
+            clock_stopped: None,
+            mouse_pos: Vector::ZERO,
+            font,
+            player_red_text,
+            player_blue_text,
+            vertex_texts,
+            panel_texts: vec![],
+            clock_texts: vec![],
+        };
+
+        if out.options.menu {
+            out.open_menu();
+        } else {
+            out.start_game();
+        }
+        out
+    }
+
+    fn open_menu(&mut self) {
+        let (red, blue) = &self.configs;
+        self.menu = Some(Menu::new(red, blue, self.options.safety, &self.font, self.options.theme));
+    }
+
+    /// Starts a new game with the players in `configs`. All randomness of
+    /// the players is derived from one seed, which is printed (and recorded),
+    /// so that the game can be reproduced.
+    fn start_game(&mut self) {
+        let seed = self.next_seed.take().unwrap_or_else(|| thread_rng().gen());
+        println!("Using seed {}", seed);
+        let (seed_red, seed_blue) = derive_seeds(seed);
+        let (red, blue) = &self.configs;
+        self.player_red = red.create(EdgeState::Red, seed_red, true);
+        self.player_blue = blue.create(EdgeState::Blue, seed_blue, true);
+        self.record = GameRecord {
+            red: Some(red.to_string()),
+            blue: Some(blue.to_string()),
+            seed: Some(seed),
+            moves: vec![],
+        };
+
+        self.state = GameState::new();
+        self.menu = None;
+        self.history_view = None;
+        self.clock_stopped = None;
+        self.hovered_edge = None;
+        self.armed_edge = None;
+        self.notice = None;
+        self.last_move_time = Instant::now();
+        self.clock = self.options.time_control.map(Clock::new);
+        self.forfeit = None;
+        self.start_turn();
+
+        let state = self.state.clone();
+        self.computer_players().for_each(|p| p.on_game_start(&state));
+        self.update_panel();
+    }
+
+    /// Starts the clock of the active player if it's a human player. The
+    /// clocks of computer players are started in `update`.
+    fn start_turn(&mut self) {
+        self.turn_start = if self.human_to_move() { Some(Instant::now()) } else { None };
+    }
+
+    /// Returns `true` if the player to move is a human player (a `None`
+    /// player).
+    fn human_to_move(&self) -> bool {
+        match self.state.to_move() {
+            EdgeState::Blue => self.player_blue.is_none(),
+            _ => self.player_red.is_none(),
+        }
+    }
+
+    /// Returns the time left on the clock of `color` (taking the running
+    /// clock into account).
+    fn time_left(&self, color: EdgeState) -> Option<Duration> {
+        let clock = self.clock.as_ref()?;
+        let running = match self.turn_start {
+            Some(start) if color == self.state.to_move() && self.winner().is_none() => {
+                self.clock_stopped.unwrap_or_else(Instant::now).saturating_duration_since(start)
+            }
+            _ => Duration::ZERO,
+        };
+        Some(clock.remaining(color).saturating_sub(running))
+    }
+
+    /// Re-renders the clocks if their text changed.
+    fn update_clock_texts(&mut self) {
+        let theme = self.options.theme;
+        let clocks = [(EdgeState::Red, theme.red), (EdgeState::Blue, theme.blue)];
+        let texts = clocks.iter()
+            .filter_map(|&(c, rgb)| {
+                Some((format!("{:?} {}", c, format_clock(self.time_left(c)?)), rgb))
+            })
+            .collect::<Vec<_>>();
+
+        if texts.len() != self.clock_texts.len() {
+            self.clock_texts.clear();
+        }
+        for (i, (text, rgb)) in texts.into_iter().enumerate() {
+            if self.clock_texts.get(i).map(|(t, _)| t) == Some(&text) {
+                continue;
+            }
+            let image = self.font.render(&text, &FontStyle::new(PANEL_TEXT_SIZE, color(rgb)))
+                .expect("failed to render text");
+            if i < self.clock_texts.len() {
+                self.clock_texts[i] = (text, image);
+            } else {
+                self.clock_texts.push((text, image));
+            }
+        }
+    }
+
+    /// Returns the winner of the game: the player who didn't complete a
+    /// triangle or didn't forfeit the game.
+    fn winner(&self) -> Option<EdgeState> {
+        self.state.winner().or_else(|| self.forfeit.map(|(loser, _)| loser.opponent()))
+    }
+
+    /// Recomputes the layout if the window size changed. The view is set to
+    /// the window size, so that one unit is one pixel and nothing is
+    /// stretched.
+    fn update_layout(&mut self, window: &mut Window) {
+        let size = window.screen_size();
+        if size != self.layout.size {
+            window.set_view(View::new(Rectangle::new_sized(size)));
+            self.layout = Layout::new(size);
+        }
+    }
+
+    /// Re-renders the lines of the side panel: the player types, the game
+    /// status and the numbered moves (in the color of the player who made
+    /// them).
+    fn update_panel(&mut self) {
+        let num_moves = self.record.moves.len();
+        let status = match (&self.history_view, self.winner()) {
+            (Some((n, _)), _) => format!("Move {} of {} (End: back)", n, num_moves),
+            (None, Some(winner)) => {
+                let how = match self.forfeit {
+                    Some((_, Forfeit::Time)) => " on time",
+                    Some((_, Forfeit::InvalidMove)) => " by forfeit",
+                    None => "",
+                };
+                format!("{:?} won{} after {} moves", winner, how, num_moves)
+            }
+            (None, None) => format!("Move {}: {:?} to play", num_moves + 1, self.state.to_move()),
+        };
+        let status = if self.paused { format!("{} (paused)", status) } else { status };
+        let player = |name: &Option<String>| name.as_deref().unwrap_or("human").to_string();
+
+        let theme = &self.options.theme;
+        let mut lines = vec![
+            (0, format!("Red: {}", player(&self.record.red)), theme.red),
+            (1, format!("Blue: {}", player(&self.record.blue)), theme.blue),
+            (CLOCK_LINE + 1, status, theme.point),
+        ];
+        if self.returns_to_menu() {
+            lines.push((CLOCK_LINE + 2, "Enter: new game".to_string(), theme.point));
+        } else if let Some(notice) = &self.notice {
+            lines.push((CLOCK_LINE + 2, notice.clone(), theme.warning));
+        }
+        for (i, edge) in self.record.moves.iter().enumerate() {
+            let rgb = if i % 2 == 0 { theme.red } else { theme.blue };
+            lines.push((FIRST_MOVE_LINE + i, format!("{}. {}", i + 1, edge), rgb));
+        }
+
+        let font = &self.font;
+        self.panel_texts = lines.into_iter()
+            .map(|(line, text, rgb)| {
+                let image = font.render(&text, &FontStyle::new(PANEL_TEXT_SIZE, color(rgb)))
+                    .expect("failed to render text");
+                (line, image)
+            })
+            .collect();
+    }
+
+    /// Shows the position after the first `n` moves, or the current position
+    /// if `n` is the number of moves played so far.
+    fn view_history(&mut self, n: usize) {
+        self.history_view = if n < self.record.moves.len() {
+            let state = play_moves(&self.record.moves[..n])
+                .expect("bug: recorded moves are invalid");
+            Some((n, state))
+        } else {
+            None
+        };
+
+        // The running clock is stopped while an earlier position is viewed.
+        match (&self.history_view, self.clock_stopped) {
+            (Some(_), None) => self.clock_stopped = Some(Instant::now()),
+            (None, Some(stopped)) => {
+                self.turn_start = self.turn_start.map(|start| start + stopped.elapsed());
+                self.clock_stopped = None;
+            }
+            _ => {}
+        }
+
+        self.hovered_edge = None;
+        self.armed_edge = None;
+        self.notice = None;
+        self.last_move_time = Instant::now();
+        self.update_panel();
+    }
+
+    /// Returns the position on screen: the current one or the viewed earlier
+    /// one.
+    fn shown_state(&self) -> &GameState {
+        match &self.history_view {
+            Some((_, state)) => state,
+            None => &self.state,
+        }
+    }
+
+    /// Returns `true` if the game is over and the setup menu can be opened
+    /// (with Enter).
+    fn returns_to_menu(&self) -> bool {
+        self.options.menu && !self.options.auto_restart && self.winner().is_some()
+    }
+
+    /// Returns an iterator over all non-human players.
+    fn computer_players(&mut self) -> impl Iterator<Item = &mut Box<dyn Player>> {
+        self.player_red.iter_mut().chain(self.player_blue.iter_mut())
+    }
+
+    /// Checks if we are waiting for user input. This is the case when it's a
+    /// `None` player's turn.
+    fn waiting_for_input(&self) -> bool {
+        self.winner().is_none() && self.history_view.is_none() && self.human_to_move()
+    }
+
+    /// Returns `true` if the safety mode is active and coloring `edge` would
+    /// make the active player lose immediately. If every move loses, no move
+    /// is unsafe (otherwise the game couldn't end in `Safety::Forbid` mode).
+    fn is_unsafe(&self, edge: Edge) -> bool {
+        let color = self.state.to_move();
+        self.options.safety != Safety::Off
+            && self.state.would_create_triangle(edge, color)
+            && self.state.safe_mask(color) != 0
+    }
+
+    /// Handles a click of a human player on the given edge.
+    fn select_edge(&mut self, edge: Edge) {
+        if !self.is_unsafe(edge) {
+            self.execute_move(edge);
+            return;
+        }
+
+        match self.options.safety {
+            Safety::Off => unreachable!(),
+            Safety::Forbid => {
+                self.notice = Some(format!("{} loses the game!", edge));
+                self.update_panel();
+            }
+            Safety::Warn => {
+                if self.armed_edge == Some(edge) {
+                    self.execute_move(edge);
+                } else {
+                    self.notice = Some(format!("{} loses! Select it again to confirm", edge));
+                    self.armed_edge = Some(edge);
+                    self.update_panel();
+                }
+            }
+        }
+    }
+
+    /// Colors the given edge in the color of the active player. An illegal
+    /// move (which only computer players can make) forfeits the game.
+    fn execute_move(&mut self, edge: Edge) {
+        let color = self.state.to_move();
+        if self.state.legal_mask() & edge.bit() == 0 {
+            println!("Player {:?} made the invalid move {}", color, edge);
+            self.forfeit(color, Forfeit::InvalidMove);
+            return;
+        }
+
+        if let Some(clock) = &mut self.clock {
+            let elapsed = self.turn_start.map(|start| start.elapsed()).unwrap_or_default();
+            if !clock.record_move(color, elapsed) {
+                self.forfeit(color, Forfeit::Time);
+                return;
+            }
+        }
+
+        let outcome = self.state.play(edge).expect("bug: legal move was rejected");
+        self.record.moves.push(edge);
+        self.computer_players().for_each(|p| p.on_move(edge, color));
+
+        match outcome {
+            Outcome::Won(winner) => {
+                let loser = winner.opponent();
+                if let Some([a, b, c]) = self.state.find_triangle(loser) {
+                    println!(
+                        "Player {:?} won! ({:?} completed the triangle {}-{}-{})",
+                        winner,
+                        loser,
+                        a.id(),
+                        b.id(),
+                        c.id(),
+                    );
+                }
+                self.end_game(winner);
+            }
+            Outcome::Ongoing => {}
+        }
+        self.armed_edge = None;
+        self.notice = None;
+        self.hovered_edge = None;
+        self.typed_vertex = None;
+        self.drag_start = None;
+        self.last_move_time = Instant::now();
+        self.start_turn();
+        self.update_panel();
+    }
+
+    /// Ends the game because `loser` ran out of time or failed to make a
+    /// valid move.
+    fn forfeit(&mut self, loser: EdgeState, reason: Forfeit) {
+        let why = match reason {
+            Forfeit::Time => "ran out of time",
+            Forfeit::InvalidMove => "failed to make a valid move",
+        };
+        println!("Player {:?} won! ({:?} {})", loser.opponent(), loser, why);
+        self.forfeit = Some((loser, reason));
+        self.end_game(loser.opponent());
+        self.armed_edge = None;
+        self.notice = None;
+        self.hovered_edge = None;
+        self.last_move_time = Instant::now();
+        self.update_panel();
+    }
+
+    /// Informs the computer players about the end of the game and saves the
+    /// game.
+    fn end_game(&mut self, winner: EdgeState) {
+        self.computer_players().for_each(|p| p.on_game_end(winner));
+
+        if let Some(path) = &self.options.record_path {
+            match self.record.write(path) {
+                Ok(()) => println!("Saved game to '{}'", path.display()),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    /// Returns the edge between the start of the current drag and the vertex
+    /// under the mouse cursor, if that edge is uncolored.
+    fn dragged_edge(&self) -> Option<Edge> {
+        let start = self.drag_start?;
+        let end = self.layout.vertex_at(self.mouse_pos)?;
+        Edge::try_between(start, end).ok()
+            .filter(|&e| self.state.edge_state(e).is_none())
+    }
+
+    /// Handles a key press of a human player: typing two vertex IDs selects
+    /// the edge between them, the arrow keys and tab cycle through all
+    /// uncolored edges and Enter colors the selected edge.
+    fn handle_key(&mut self, key: Key) {
+        match key {
+            Key::Tab | Key::Right | Key::Down => self.cycle_edge(true),
+            Key::Left | Key::Up => self.cycle_edge(false),
+            Key::Return | Key::NumpadEnter => {
+                if let Some(edge) = self.hovered_edge {
+                    self.select_edge(edge);
+                }
+            }
+            _ => {
+                if let Some(v) = vertex_key(key) {
+                    self.type_vertex(v);
+                }
+            }
+        }
+    }
+
+    /// Handles a typed vertex. The second one of two different vertices
+    /// selects the edge between them.
+    fn type_vertex(&mut self, v: Vertex) {
+        match self.typed_vertex.take() {
+            Some(first) if first != v => {
+                let edge = Edge::between(first, v);
+                if self.state.edge_state(edge).is_none() {
+                    self.hovered_edge = Some(edge);
+                } else {
+                    self.notice = Some(format!("{} is already colored", edge));
+                    self.update_panel();
+                }
+            }
+            _ => self.typed_vertex = Some(v),
+        }
+    }
+
+    /// Selects the next (or previous) uncolored edge in order of edge IDs.
+    fn cycle_edge(&mut self, forward: bool) {
+        let uncolored = Edge::all_edges()
+            .filter(|e| self.state.edge_state(*e).is_none())
+            .collect::<Vec<_>>();
+        let n = uncolored.len();
+        if n == 0 {
+            return;
+        }
+
+        let current = self.hovered_edge.and_then(|e| uncolored.iter().position(|&u| u == e));
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % n,
+            (Some(i), false) => (i + n - 1) % n,
+            (None, true) => 0,
+            (None, false) => n - 1,
+        };
+        self.hovered_edge = Some(uncolored[next]);
+        self.typed_vertex = None;
+    }
+}
+
+impl State for GuiGame {
+    fn new() -> Result<GuiGame, Error> {
+        // I would say this is fairly bad API design from `quicksilver`. This
+        // `new` method is required by the trait `State`, but it's not used if
+        // one uses `run_with` instead of `run`. There is no sensible way to
+        // implement this method.
+        panic!(
+            "Called `GuiGame::new`: this method must not be called \
+                (use `run_with` to create this state)"
+        );
+    }
+
+    // Is called in regular intervals
+    fn update(&mut self, _: &mut Window) -> Result<(), Error> {
+        if self.menu.is_some() {
+            return Ok(());
+        }
+
+        // Human players lose as soon as their time is up.
+        let to_move = self.state.to_move();
+        if self.winner().is_none() && self.time_left(to_move) == Some(Duration::ZERO) {
+            self.forfeit(to_move, Forfeit::Time);
+        }
+
+        if self.history_view.is_some() {
+            return Ok(());
+        }
+
+        let elapsed = self.last_move_time.elapsed();
+        if self.winner().is_some() {
+            if self.options.auto_restart && !self.paused && elapsed >= RESTART_DELAY {
+                self.start_game();
+            }
+            return Ok(());
+        }
+
+        // Computer players wait for the move delay unless a single step is
+        // requested.
+        let ready = self.step || (!self.paused && elapsed >= self.options.move_delay);
+
+        // Get the active player
+        let player = match self.state.to_move() {
+            EdgeState::Blue => self.player_blue.as_deref_mut(),
+            _ => self.player_red.as_deref_mut(),
+        };
+
+        // If the player is a non-human player, get a move and execute it.
+        // Its clock runs while it searches.
+        if let Some(player) = player {
+            if ready {
+                self.step = false;
+                if let Some(clock) = &self.clock {
+                    player.set_time_budget(clock.move_budget(to_move, &self.state));
+                }
+                self.turn_start = Some(Instant::now());
+                match player.next_move(&self.state) {
+                    Ok(edge) => self.execute_move(edge),
+                    Err(e) => {
+                        println!("Player {:?} failed to make a move: {}", to_move, e);
+                        self.forfeit(to_move, Forfeit::InvalidMove);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Is called each frame
+    fn draw(&mut self, window: &mut Window) -> Result<(), Error> {
+        self.update_layout(window);
+        self.update_clock_texts();
+        let theme = &self.options.theme;
+        window.clear(color(theme.background))?;
+        let layout = &self.layout;
+        if let Some(menu) = &self.menu {
+            return menu.draw(window, layout, self.mouse_pos);
+        }
+
+        let state = self.shown_state();
+        let text = match state.to_move() {
+            EdgeState::Blue => &self.player_blue_text,
+            _ => &self.player_red_text,
+        };
+        let text_center = Vector::new(layout.panel.x() / 2.0, TEXT_CENTER_Y * layout.scale);
+        window.draw(
+            &Rectangle::new_sized(text.area().size() * layout.scale).with_center(text_center),
+            Background::Img(text),
+        );
+
+        // Draw the side panel. The move of the viewed position is
+        // highlighted and texts too wide for the panel are shrunk.
+        if let Some((n, _)) = self.history_view {
+            let line = layout.panel_line(FIRST_MOVE_LINE + n - 1);
+            window.draw(&line, Background::Col(color(theme.selected).with_alpha(0.3)));
+        }
+        let clock_line = layout.panel_line(CLOCK_LINE);
+        let clock_width = clock_line.width() / 2.0;
+        let clock_areas = (0..2).map(|i| Rectangle::new(
+            clock_line.pos + Vector::new(i as f32 * clock_width, 0.0),
+            (clock_width, clock_line.height()),
+        ));
+        let texts = self.panel_texts.iter()
+            .map(|(line, text)| (layout.panel_line(*line), text))
+            .chain(clock_areas.zip(self.clock_texts.iter().map(|(_, text)| text)));
+        for (area, text) in texts {
+            let size = text.area().size() * layout.scale;
+            let size = size * (area.width() / size.x).min(1.0);
+            window.draw(&Rectangle::new(area.pos, size), Background::Img(text));
+        }
+
+        // Draw all edges. Dashed edges are drawn as one line per dash. The
+        // armed edge stays highlighted until it is confirmed or another edge
+        // is selected, even if the mouse cursor left it (e.g. after a drag).
+        let waiting = self.waiting_for_input();
+        for e in Edge::all_edges() {
+            let highlighted = self.hovered_edge == Some(e) || self.armed_edge == Some(e);
+            let (rgb, width) = match state.edge_state(e) {
+                EdgeState::None if highlighted && waiting => {
+                    if !self.is_unsafe(e) {
+                        (theme.selected, 5.0)
+                    } else if self.armed_edge == Some(e) {
+                        (theme.warning, 8.0)
+                    } else {
+                        (theme.warning, 5.0)
+                    }
+                }
+                state => theme.edge_style(state),
+            };
+
+            let (pa, pb) = layout.line(e);
+            let segments = match theme.dash(state.edge_state(e)) {
+                Some(dash) => dash.segments((pa.x, pa.y), (pb.x, pb.y), layout.scale)
+                    .into_iter()
+                    .map(|(a, b)| (Vector::from(a), Vector::from(b)))
+                    .collect(),
+                None => vec![(pa, pb)],
+            };
+            for (a, b) in segments {
+                window.draw(
+                    &Line::new(a, b).with_thickness(width * layout.scale),
+                    Background::Col(color(rgb)),
+                );
+            }
+        }
+
+        // Draw the line from the start of a drag to the mouse cursor
+        if let Some(v) = self.drag_start {
+            window.draw(
+                &Line::new(layout.corner(v), self.mouse_pos)
+                    .with_thickness(render::UNCOLORED_WIDTH * layout.scale),
+                Background::Col(color(theme.selected)),
+            );
+        }
+
+        // Draw all points with their labels. A vertex typed to select an edge
+        // is highlighted.
+        for v in Vertex::all_vertices() {
+            let p = layout.corner(v);
+            let radius = render::POINT_RADIUS * layout.scale;
+            if self.typed_vertex == Some(v) && self.waiting_for_input() {
+                window.draw(&Circle::new(p, 2.0 * radius), Background::Col(color(theme.selected)));
+            }
+            window.draw(&Circle::new(p, radius), Background::Col(color(theme.point)));
+
+            let text = &self.vertex_texts[v.id() as usize];
+            window.draw(
+                &Rectangle::new_sized(text.area().size() * layout.scale)
+                    .with_center(layout.label_center(v)),
+                Background::Img(text),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn event(&mut self, event: &Event, window: &mut Window) -> Result<(), Error> {
+        self.update_layout(window);
+        if let Some(menu) = &mut self.menu {
+            let start = match event {
+                Event::Key(key, ButtonState::Pressed) if menu.is_editing() => {
+                    menu.edit_key(*key, &self.font);
+                    false
+                }
+                Event::Typed(c) => {
+                    menu.type_char(*c, &self.font);
+                    false
+                }
+                Event::Key(Key::Escape, ButtonState::Pressed) => {
+                    window.close();
+                    false
+                }
+                Event::Key(Key::Return, ButtonState::Pressed) => true,
+                Event::MouseMoved(new_pos) => {
+                    self.mouse_pos = *new_pos;
+                    false
+                }
+                Event::MouseButton(button, ButtonState::Released) => {
+                    menu.click(self.mouse_pos, *button, &self.layout, &self.font)
+                }
+                _ => false,
+            };
+
+            if start {
+                self.configs = menu.players();
+                self.options.safety = menu.safety();
+                self.start_game();
+            }
+            return Ok(());
+        }
+
+        match event {
+            Event::Key(Key::Escape, ButtonState::Pressed) => {
+                window.close();
+            }
+
+            // Go back to the setup menu after a game
+            Event::Key(Key::Return, ButtonState::Pressed) if self.returns_to_menu() => {
+                self.open_menu();
+            }
+
+            // Pause or resume computer players
+            Event::Key(Key::Space, ButtonState::Pressed) => {
+                self.paused = !self.paused;
+                self.step = false;
+                self.update_panel();
+            }
+
+            // Let the next computer player move right away (also while
+            // paused). Ignored while a human player is to move.
+            Event::Key(Key::N, ButtonState::Pressed)
+                if self.winner().is_none() && !self.human_to_move() =>
+            {
+                self.step = true;
+            }
+
+            // Return from viewing an earlier position to the current one
+            Event::Key(Key::End, ButtonState::Pressed) if self.history_view.is_some() => {
+                self.view_history(self.record.moves.len());
+            }
+
+            // Print a perfect play analysis of the position on screen
+            Event::Key(Key::A, ButtonState::Pressed) if self.shown_state().winner().is_none() => {
+                let state = self.shown_state();
+                print_analysis(state, state.to_move());
+            }
+
+            // Keyboard input of human players (see `handle_key`)
+            Event::Key(key, ButtonState::Pressed) if self.waiting_for_input() => {
+                self.handle_key(*key);
+            }
+
+            Event::MouseMoved(new_pos) => {
+                self.mouse_pos = *new_pos;
+                self.hovered_edge = match (self.history_view.as_ref(), self.options.input_mode) {
+                    (Some(_), _) => None,
+                    (None, InputMode::Click) => self.layout.edge_at(&self.state, *new_pos),
+                    (None, InputMode::Drag) => self.dragged_edge(),
+                };
+                let can_drag = self.options.input_mode == InputMode::Drag
+                    && self.waiting_for_input()
+                    && (self.drag_start.is_some() || self.layout.vertex_at(*new_pos).is_some());
+                let clickable = can_drag
+                    || self.layout.move_at(*new_pos, self.record.moves.len()).is_some();
+
+                let cursor = match self.hovered_edge {
+                    Some(e) if self.options.safety == Safety::Forbid && self.is_unsafe(e) => {
+                        MouseCursor::NotAllowed
+                    }
+                    Some(_) => MouseCursor::Hand,
+                    None if clickable => MouseCursor::Hand,
+                    None => MouseCursor::Default,
+                };
+                window.set_cursor(cursor);
+            }
+
+            // Start dragging from a vertex
+            Event::MouseButton(MouseButton::Left, ButtonState::Pressed)
+                if self.options.input_mode == InputMode::Drag && self.waiting_for_input() =>
+            {
+                self.drag_start = self.layout.vertex_at(self.mouse_pos);
+            }
+
+            // Clicking a move in the side panel shows the position after that
+            // move. Otherwise, if we are waiting for input and the mouse
+            // hovers over a line (or a drag ends on a vertex), that line is
+            // selected.
+            Event::MouseButton(MouseButton::Left, ButtonState::Released) => {
+                let num_moves = self.record.moves.len();
+                let dragged = self.drag_start.take().is_some();
+                if let Some(i) = self.layout.move_at(self.mouse_pos, num_moves) {
+                    self.view_history(i + 1);
+                } else if let Some(hovered_edge) = self.hovered_edge {
+                    let selected = dragged || self.options.input_mode == InputMode::Click;
+                    if selected && self.waiting_for_input() {
+                        self.select_edge(hovered_edge);
+                    }
+                }
+                if dragged {
+                    self.hovered_edge = None;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats the time on a clock as `m:ss.s`.
+fn format_clock(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths % 600 / 10, tenths % 10)
+}
+
+/// Returns the vertex whose ID is written on `key` (on the main keyboard or
+/// the numpad).
+fn vertex_key(key: Key) -> Option<Vertex> {
+    let id = match key {
+        Key::Key0 | Key::Numpad0 => 0,
+        Key::Key1 | Key::Numpad1 => 1,
+        Key::Key2 | Key::Numpad2 => 2,
+        Key::Key3 | Key::Numpad3 => 3,
+        Key::Key4 | Key::Numpad4 => 4,
+        Key::Key5 | Key::Numpad5 => 5,
+        _ => return None,
+    };
+    Some(Vertex::new(id))
+}
+
+/// Calculates the nearest distance of the point `p` to the line segment
+/// from `a` to `b`.
+fn distance_to_point((a, b): (Vector, Vector), p: Vector) -> f32 {
+    // We pretend that `a` is the origin by subtracting a.
+    let a_to_b = b - a;
+    let a_to_p = p - a;
+
+    // The idea is to project P onto the line via the dot product. But by
+    // clamping the result between 0 and 1 we can make sure that P is projected
+    // on the line segment instead. The distance between that resulting point
+    // and the original point is the correct distance.
+    //
+    //     B                     B
+    //     |                     |
+    //     |    P    project     |----P
+    //     |   /      ====>      |   /
+    //     |  /                  |  /
+    //     | /                   | /
+    //     A                     A
+    //
+
+    // First we calculate the dot product and divide it by the distance from a
+    // to b. If that value is between 0 and 1 it means that the projected
+    // points lies on the line segment.
+    let scale = a_to_b.dot(a_to_p) / a_to_b.len2();
+
+    // Then we clamp this scale between 0 and 1 to make sure it lies on the
+    // line segment.
+    let scale = scale.clamp(0.0, 1.0);
+
+    // Actually calculate the projected point
+    let projected_on_line_segment = a_to_b * scale;
+
+    // Distance from projected point to P is our result
+    a_to_p.distance(projected_on_line_segment)
+}
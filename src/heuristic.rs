@@ -0,0 +1,111 @@
+//! A heuristic evaluation of positions as weighted sum of simple features.
+//!
+//! The weights can be tuned with `evolution::evolve`. A weights file contains
+//! one line `<feature>: <weight>` per feature (see `FEATURE_NAMES`); features
+//! missing in the file keep their default weight. The file is a data file
+//! (see `io`).
+
+use std::{fmt, path::Path};
+
+use crate::game::TRIANGLE_MASKS;
+use crate::io::{self, DataFile};
+
+
+/// The number of features.
+pub const NUM_FEATURES: usize = 5;
+
+/// The names of all features, in the order used by `Weights`.
+///
+/// All features are computed for the position right after a move, from the
+/// view of the player who made it (`own`):
+/// - `safe_own`/`safe_opponent`: uncolored edges the player/opponent can
+///   color without completing a triangle.
+/// - `threats_own`/`threats_opponent`: triangles with two edges of the
+///   player/opponent and an uncolored third edge.
+/// - `mixed_triangles`: triangles with edges of both colors, which can't be
+///   completed by anyone anymore.
+pub const FEATURE_NAMES: [&str; NUM_FEATURES] = [
+    "safe_own",
+    "safe_opponent",
+    "threats_own",
+    "threats_opponent",
+    "mixed_triangles",
+];
+
+/// The weight of every feature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weights(pub [f64; NUM_FEATURES]);
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights([1.0, -1.0, -0.5, 0.5, 0.2])
+    }
+}
+
+impl Weights {
+    /// Returns the value of the position after a move (higher is better for
+    /// the player who made the move).
+    pub fn evaluate(&self, own: u16, other: u16) -> f64 {
+        features(own, other).iter()
+            .zip(&self.0)
+            .map(|(f, w)| f * w)
+            .sum()
+    }
+
+    /// Reads a weights file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = DataFile::read(path)?;
+        let mut out = Self::default();
+        for line in file.lines() {
+            let (key, value) = line.key_value()?;
+            let index = FEATURE_NAMES.iter()
+                .position(|&name| name == key)
+                .ok_or_else(|| line.error(format_args!("unknown feature '{}'", key)))?;
+            out.0[index] = value.parse().map_err(|_| line.invalid())?;
+        }
+
+        Ok(out)
+    }
+
+    /// Writes the weights to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let out = format!("# Sim heuristic weights\n{}", self);
+        io::write(path, &out)
+    }
+}
+
+/// Writes one line `<feature>: <weight>` per feature.
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, weight) in FEATURE_NAMES.iter().zip(&self.0) {
+            writeln!(f, "{}: {:.4}", name, weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes all features (see `FEATURE_NAMES`) of the position where the
+/// player owns the edges in `own` and the opponent the ones in `other`.
+pub fn features(own: u16, other: u16) -> [f64; NUM_FEATURES] {
+    let free = !(own | other) & ((1 << 15) - 1);
+
+    let mut unsafe_own = 0;
+    let mut unsafe_opponent = 0;
+    let mut out = [0.0; NUM_FEATURES];
+    for &t in &TRIANGLE_MASKS {
+        let (own_edges, other_edges) = ((t & own).count_ones(), (t & other).count_ones());
+        if own_edges > 0 && other_edges > 0 {
+            out[4] += 1.0;
+        } else if own_edges == 2 && t & free != 0 {
+            out[2] += 1.0;
+            unsafe_own |= t & free;
+        } else if other_edges == 2 && t & free != 0 {
+            out[3] += 1.0;
+            unsafe_opponent |= t & free;
+        }
+    }
+
+    out[0] = (free & !unsafe_own).count_ones() as f64;
+    out[1] = (free & !unsafe_opponent).count_ones() as f64;
+    out
+}
@@ -0,0 +1,84 @@
+//! Reading and writing the plain text data files of this crate: game
+//! records, ratings, opening books, value tables, heuristic weights and
+//! themes.
+//!
+//! All data files are line based. Whitespace around lines is ignored, as
+//! are empty lines and comment lines starting with `#`. What the other lines
+//! contain is described by each file type.
+
+use std::{fmt, fs, io, path::{Path, PathBuf}};
+
+
+/// The content of a data file.
+#[derive(Clone, Debug)]
+pub struct DataFile {
+    path: PathBuf,
+    content: String,
+}
+
+impl DataFile {
+    /// Reads the file at `path`.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| read_error(path, &e))?;
+        Ok(Self { path: path.to_owned(), content })
+    }
+
+    /// Like `read`, but returns `None` if the file doesn't exist.
+    pub fn read_if_exists(path: &Path) -> Result<Option<Self>, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Some(Self { path: path.to_owned(), content })),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(read_error(path, &e)),
+        }
+    }
+
+    /// Returns all lines except empty lines and comments.
+    pub fn lines(&self) -> impl Iterator<Item = Line<'_>> {
+        let path = self.path.as_path();
+        self.content.lines()
+            .enumerate()
+            .map(move |(i, text)| Line { text: text.trim(), number: i + 1, path })
+            .filter(|line| !line.text.is_empty() && !line.text.starts_with('#'))
+    }
+}
+
+/// A line of a data file (see `DataFile::lines`).
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    /// The text of the line without surrounding whitespace.
+    pub text: &'a str,
+
+    /// The line number, starting at 1.
+    pub number: usize,
+
+    path: &'a Path,
+}
+
+impl<'a> Line<'a> {
+    /// Returns an error message about this line: `msg` followed by the
+    /// position of the line.
+    pub fn error(&self, msg: impl fmt::Display) -> String {
+        format!("{} in line {} of '{}'", msg, self.number, self.path.display())
+    }
+
+    /// Returns the error message for a line with invalid content.
+    pub fn invalid(&self) -> String {
+        self.error(format_args!("invalid line '{}'", self.text))
+    }
+
+    /// Splits a line of the form `key: value` into key and value (both
+    /// without surrounding whitespace).
+    pub fn key_value(&self) -> Result<(&'a str, &'a str), String> {
+        let (key, value) = self.text.split_once(':').ok_or_else(|| self.invalid())?;
+        Ok((key.trim(), value.trim()))
+    }
+}
+
+/// Writes `content` to the file at `path`.
+pub fn write(path: &Path, content: &str) -> Result<(), String> {
+    fs::write(path, content).map_err(|e| format!("failed to write '{}': {}", path.display(), e))
+}
+
+fn read_error(path: &Path, e: &io::Error) -> String {
+    format!("failed to read '{}': {}", path.display(), e)
+}
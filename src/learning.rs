@@ -0,0 +1,141 @@
+//! Learning position values by self-play (tabular TD learning).
+//!
+//! The value table stores, for positions right after a move, the estimated
+//! probability that the player who made the move wins. Positions are stored
+//! from the view of that player, so the same table is used for red and blue.
+//! There are few enough positions in Sim to store every one of them.
+//!
+//! A table file contains one position per line: `<position> <value>`, with
+//! the position written from the view of the player who just moved (see
+//! `notation`). The file is a data file (see `io`).
+
+use std::{collections::HashMap, path::Path, sync::{Arc, Mutex}};
+
+use crate::{
+    game::EdgeState,
+    io::{self, DataFile},
+    notation::{parse_view, view_to_string},
+    player::{Learned, Player},
+    runner::play_game,
+};
+
+
+/// The value of positions that were never seen during training.
+pub const UNKNOWN_VALUE: f32 = 0.5;
+
+/// Estimated winning probabilities of positions.
+#[derive(Clone, Debug, Default)]
+pub struct ValueTable {
+    values: HashMap<(u16, u16), f32>,
+}
+
+impl ValueTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of positions with a learned value.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the probability that the player owning the edges in `own`
+    /// wins, when the opponent (owning `other`) is to move.
+    pub fn value(&self, own: u16, other: u16) -> f32 {
+        self.values.get(&(own, other)).cloned().unwrap_or(UNKNOWN_VALUE)
+    }
+
+    /// Moves the value of the given position towards `target` by the
+    /// fraction `learning_rate`.
+    pub fn update(&mut self, own: u16, other: u16, target: f32, learning_rate: f32) {
+        let value = self.values.entry((own, other)).or_insert(UNKNOWN_VALUE);
+        *value += learning_rate * (target - *value);
+    }
+
+    /// Reads a table file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = DataFile::read(path)?;
+        let mut out = Self::new();
+        for line in file.lines() {
+            let invalid = || line.invalid();
+            let fields = line.text.split_whitespace().collect::<Vec<_>>();
+            if fields.len() != 2 {
+                return Err(invalid());
+            }
+
+            let key = parse_view(fields[0]).map_err(|_| invalid())?;
+            let value = fields[1].parse().map_err(|_| invalid())?;
+            out.values.insert(key, value);
+        }
+
+        Ok(out)
+    }
+
+    /// Writes the table to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut entries = self.values.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|&(&(own, other), _)| ((own | other).count_ones(), own, other));
+
+        let mut out = String::from("# Sim value table: <position> <value>\n");
+        for (&(own, other), value) in entries {
+            out += &format!("{} {:.4}\n", view_to_string(own, other), value);
+        }
+
+        io::write(path, &out)
+    }
+}
+
+/// Settings for `train`.
+#[derive(Clone, Copy, Debug)]
+pub struct TrainConfig {
+    /// How far values are moved towards their target after each game.
+    pub learning_rate: f32,
+
+    /// The probability of making a random move instead of the best known
+    /// one, so that new positions are explored.
+    pub exploration: f64,
+
+    /// Seed for all random decisions.
+    pub seed: u64,
+}
+
+/// Trains `table` by letting two `Learned` players sharing the table play
+/// `games` games against each other. After each game, both players move the
+/// values of their positions towards the value of the next position (TD(0),
+/// from the end of the game backwards).
+///
+/// `on_progress` is called after every game with the number of finished
+/// games and the table.
+pub fn train(
+    table: ValueTable,
+    games: u32,
+    config: TrainConfig,
+    mut on_progress: impl FnMut(u32, &ValueTable),
+) -> ValueTable {
+    let table = Arc::new(Mutex::new(table));
+    let (seed_red, seed_blue) = crate::player::derive_seeds(config.seed);
+
+    let mut red = Learned::with_table(EdgeState::Red, seed_red, table.clone());
+    let mut blue = Learned::with_table(EdgeState::Blue, seed_blue, table.clone());
+    for player in [&mut red, &mut blue] {
+        player.set_exploration(config.exploration);
+        player.set_learning_rate(Some(config.learning_rate));
+    }
+
+    for game in 0..games {
+        red.reset();
+        blue.reset();
+        play_game(&mut red, &mut blue, None);
+        on_progress(game + 1, &table.lock().unwrap());
+    }
+
+    drop((red, blue));
+    Arc::try_unwrap(table)
+        .expect("table is still shared")
+        .into_inner()
+        .unwrap()
+}
@@ -0,0 +1,18 @@
+//! Implementation of the Sim pencil game: the game logic, tools to analyze
+//! positions and computer players. The GUI lives in the binary.
+
+pub mod analysis;
+pub mod book;
+pub mod clock;
+pub mod evolution;
+pub mod game;
+pub mod heuristic;
+pub mod io;
+pub mod learning;
+pub mod notation;
+pub mod perft;
+pub mod player;
+pub mod rating;
+pub mod render;
+pub mod runner;
+pub mod theme;
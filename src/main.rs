@@ -1,82 +1,632 @@
+use std::{
+    fmt::Display,
+    path::PathBuf,
+    process,
+    time::{Duration, Instant},
+};
+
 use quicksilver::{
     geom::Vector,
+    graphics::ResizeStrategy,
     lifecycle::{Settings, run_with},
 };
+use rand::{Rng, thread_rng};
 use structopt::StructOpt;
 
-use crate::{
-    game::EdgeState,
-    gui::GuiGame,
-    player::{Player, DumbRandom, Random, MiniMax},
+use sim::{
+    analysis::print_analysis,
+    game::{Edge, GameState, EdgeState},
+    notation,
+    perft::perft,
+    book::OpeningBook,
+    clock::TimeControl,
+    evolution::{self, EvolveConfig},
+    heuristic::Weights,
+    learning::{self, TrainConfig, ValueTable},
+    rating::Ratings,
+    render::{self, Highlights, Scene},
+    theme::Theme,
+    runner::{Forfeit, play_match},
+    player::{PlayerConfig, PLAYER_HELP, derive_seeds, seeded_rng},
 };
 
-mod game;
+use crate::gui::{GuiGame, GuiOptions, InputMode, Safety};
+
 mod gui;
-mod player;
 
 
 fn main() {
     // Parse command line parameters and prepare players
     let opt = Opt::from_args();
-    let player_red = opt.player_red.computer_player();
-    let player_blue = opt.player_blue.computer_player();
+    if let Some(cmd) = opt.cmd {
+        cmd.run();
+        return;
+    }
+
+    let (red, blue, seed) = (opt.player_red, opt.player_blue, opt.seed);
+    let options = GuiOptions {
+        safety: opt.safety,
+        input_mode: opt.input,
+        theme: opt.theme,
+        time_control: opt.time,
+        record_path: opt.record,
+        move_delay: Duration::from_millis(opt.move_delay),
+        auto_restart: opt.auto_restart,
+        menu: !opt.no_menu,
+    };
 
     // Start the main loop of the GUI framework
-    run_with("Sim", Vector::new(1000, 1000), Settings::default(), || {
-        Ok(GuiGame::new(player_red, player_blue))
+    // The GUI adapts its layout to the window size, so the content must not
+    // be scaled by quicksilver.
+    let settings = Settings {
+        resize: ResizeStrategy::Stretch,
+        min_size: Some(Vector::new(300, 300)),
+        ..Settings::default()
+    };
+    run_with("Sim", gui::window_size(opt.window_size), settings, || {
+        Ok(GuiGame::new(red, blue, seed, options))
     });
 }
 
-/// A player choosen via command line.
-enum PlayerInput {
-    Human,
-    Other(Box<dyn Player>),
+#[derive(StructOpt)]
+#[structopt(
+    name = "sim",
+    about = "Implementation of the Sim pencil game",
+    usage = "sim [FLAGS] [OPTIONS] <player_red> <player_blue>",
+    raw(after_help = "PLAYER_HELP"),
+)]
+struct Opt {
+    /// The player with color red (the starting player). See 'PLAYERS' below
+    /// for all players and their options.
+    #[structopt(default_value = "human")]
+    player_red: PlayerConfig,
+
+    /// The player with color blue.
+    #[structopt(default_value = "random")]
+    player_blue: PlayerConfig,
+
+    /// How moves of human players that would immediately lose are treated.
+    /// 'off': no special treatment, 'warn': such edges are highlighted and
+    /// need to be clicked twice, 'forbid': such edges cannot be selected.
+    #[structopt(long = "safety", default_value = "off")]
+    safety: Safety,
+
+    /// How edges are selected with the mouse. 'click': clicking close to an
+    /// edge, 'drag': pressing the mouse button on one vertex and releasing it
+    /// on another one.
+    #[structopt(long = "input", default_value = "click")]
+    input: InputMode,
+
+    /// The colors of the board: 'dark', 'light', 'high-contrast',
+    /// 'colorblind' (which also uses dashed lines for blue) or the path of a
+    /// theme file (see the README).
+    #[structopt(long = "theme", default_value = "dark")]
+    theme: Theme,
+
+    /// The time control, e.g. '60+2' (60 seconds per game plus 2 seconds per
+    /// move) or '5/move'. The clocks are shown next to the board and a player
+    /// who runs out of time loses.
+    #[structopt(long = "time")]
+    time: Option<TimeControl>,
+
+    /// Seed for all random decisions of the players in the first game.
+    /// Playing again with the same seed and players results in the same game
+    /// (as long as human players make the same moves). A random seed is used
+    /// if not specified and for all later games.
+    #[structopt(long = "seed")]
+    seed: Option<u64>,
+
+    /// Saves the game (including the seed) to the given file when it ends.
+    #[structopt(long = "record", parse(from_os_str))]
+    record: Option<PathBuf>,
+
+    /// The initial size of the board in pixels. The window is wider to make
+    /// room for the side panel and can be resized while playing.
+    #[structopt(long = "window-size", default_value = "800")]
+    window_size: u32,
+
+    /// The minimum time in milliseconds between the previous move and a move
+    /// of a computer player. Press space to pause or resume the computer
+    /// players and 'N' to let the next one move right away.
+    #[structopt(long = "move-delay", default_value = "500")]
+    move_delay: u64,
+
+    /// Starts a new game with the same players a few seconds after a game
+    /// ends (e.g. for continuous games between computer players).
+    #[structopt(long = "auto-restart")]
+    auto_restart: bool,
+
+    /// Starts the game right away instead of showing the setup menu, where
+    /// the players given on the command line are preselected.
+    #[structopt(long = "no-menu")]
+    no_menu: bool,
+
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+/// Tools that don't open the GUI.
+#[derive(StructOpt)]
+enum Command {
+    /// Analyzes a position assuming perfect play from both sides.
+    #[structopt(name = "analyze")]
+    Analyze {
+        /// The position to analyze. Either a position string with one
+        /// character per edge ('.', 'r' or 'b'; e.g. 'r.....b........'), a
+        /// list of moves (e.g. '0-1 2-3') or the path to a game file. Defaults
+        /// to the empty board.
+        #[structopt(
+            default_value = "",
+            raw(hide_default_value = "true"),
+            parse(try_from_str = "parse_position"),
+        )]
+        position: GameState,
+    },
+
+    /// Counts all move sequences up to the given depth (perft). Sequences stop
+    /// early when a move completes a triangle.
+    #[structopt(name = "perft")]
+    Perft {
+        /// The number of moves in each sequence.
+        depth: u8,
+
+        /// The starting position (same format as for 'analyze'). Defaults to
+        /// the empty board.
+        #[structopt(
+            default_value = "",
+            raw(hide_default_value = "true"),
+            parse(try_from_str = "parse_position"),
+        )]
+        position: GameState,
+    },
+
+    /// Plays games between two computer players without opening the GUI and
+    /// prints how many games each player won.
+    #[structopt(name = "match", raw(after_help = "PLAYER_HELP"))]
+    Match {
+        /// The player with color red (the starting player).
+        player_red: PlayerConfig,
+
+        /// The player with color blue.
+        player_blue: PlayerConfig,
+
+        /// The number of games to play.
+        #[structopt(long = "games", default_value = "100")]
+        games: u32,
+
+        /// The time control, e.g. '60+2' (60 seconds per game plus 2 seconds
+        /// per move) or '5/move'. A player who runs out of time loses.
+        #[structopt(long = "time")]
+        time: Option<TimeControl>,
+
+        /// Seed for all random decisions of the players. A random seed is
+        /// used if not specified.
+        #[structopt(long = "seed")]
+        seed: Option<u64>,
+    },
+
+    /// Lets every player play against every other player (with both colors),
+    /// updates the Elo ratings of all players and prints a leaderboard.
+    #[structopt(name = "tournament", raw(after_help = "PLAYER_HELP"))]
+    Tournament {
+        /// The participating players (at least two). Different
+        /// configurations of the same player are rated separately.
+        #[structopt(raw(required = "true", min_values = "2"))]
+        players: Vec<PlayerConfig>,
+
+        /// The number of games per pairing and color.
+        #[structopt(long = "games", default_value = "20")]
+        games: u32,
+
+        /// The time control, e.g. '60+2' (60 seconds per game plus 2 seconds
+        /// per move) or '5/move'. A player who runs out of time loses.
+        #[structopt(long = "time")]
+        time: Option<TimeControl>,
+
+        /// Seed for all random decisions of the players. A random seed is
+        /// used if not specified.
+        #[structopt(long = "seed")]
+        seed: Option<u64>,
+
+        /// The file in which ratings are kept across tournaments. It's created
+        /// if it doesn't exist.
+        #[structopt(long = "ratings", default_value = "ratings.txt", parse(from_os_str))]
+        ratings: PathBuf,
+    },
+
+    /// Generates an opening book with perfect play moves for all early
+    /// positions. Use it with the 'book' player.
+    #[structopt(name = "book")]
+    Book {
+        /// The file the book is written to.
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+
+        /// Positions with less than this many colored edges are stored.
+        #[structopt(long = "plies", default_value = "8")]
+        plies: u8,
+    },
+
+    /// Trains a value table for the 'learned' player by self-play. If the
+    /// output file already exists, training continues with that table.
+    #[structopt(name = "train")]
+    Train {
+        /// The file the table is written to.
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+
+        /// The number of self-play games.
+        #[structopt(long = "games", default_value = "100000")]
+        games: u32,
+
+        /// How far values are moved towards their target after each game.
+        #[structopt(long = "learning-rate", default_value = "0.1")]
+        learning_rate: f32,
+
+        /// The probability of making a random move during training.
+        #[structopt(long = "exploration", default_value = "0.1")]
+        exploration: f64,
+
+        /// Seed for all random decisions. A random seed is used if not
+        /// specified.
+        #[structopt(long = "seed")]
+        seed: Option<u64>,
+    },
+
+    /// Renders a position to an SVG or PNG image (depending on the file
+    /// extension of the output). The last move and a completed triangle are
+    /// highlighted.
+    #[structopt(name = "render")]
+    Render {
+        /// The position to render (same format as for 'analyze'). The last
+        /// move is only known for move lists and game files. Defaults to the
+        /// empty board.
+        #[structopt(long = "position", default_value = "")]
+        position: String,
+
+        /// Highlights this edge as the last move instead of the last move of
+        /// the position.
+        #[structopt(long = "last-move", parse(try_from_str = "notation::parse_edge"))]
+        last_move: Option<Edge>,
+
+        /// The width and height of the image in pixels.
+        #[structopt(long = "size", default_value = "500")]
+        size: u32,
+
+        /// The colors: a built-in theme ('dark', 'light', 'high-contrast',
+        /// 'colorblind') or a theme file.
+        #[structopt(long = "theme", default_value = "dark")]
+        theme: Theme,
+
+        /// The image file to write ('.svg' or '.png').
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: PathBuf,
+    },
+
+    /// Renders every move of a game, either as animated GIF or as numbered
+    /// SVG or PNG images (e.g. 'game.png' becomes 'game-00.png',
+    /// 'game-01.png', ...). Each move is highlighted and the last image shows
+    /// the losing triangle.
+    #[structopt(name = "render-game")]
+    RenderGame {
+        /// The game to render: a game file or a move list.
+        game: String,
+
+        /// The width and height of the images in pixels.
+        #[structopt(long = "size", default_value = "400")]
+        size: u32,
+
+        /// How long each move is shown in the GIF, in milliseconds.
+        #[structopt(long = "delay", default_value = "1000")]
+        delay: u32,
+
+        /// The colors: a built-in theme ('dark', 'light', 'high-contrast',
+        /// 'colorblind') or a theme file.
+        #[structopt(long = "theme", default_value = "dark")]
+        theme: Theme,
+
+        /// The output file ('.gif', '.svg' or '.png').
+        #[structopt(short = "o", long = "output", parse(from_os_str))]
+        output: PathBuf,
+    },
+
+    /// Tunes the weights of the 'heuristic' player with a genetic algorithm
+    /// and writes the best weights to a file. If the output file already
+    /// exists, evolution starts from the weights in that file.
+    #[structopt(name = "evolve")]
+    Evolve {
+        /// The file the weights are written to.
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+
+        /// The number of generations.
+        #[structopt(long = "generations", default_value = "30")]
+        generations: u32,
+
+        /// The number of candidates per generation.
+        #[structopt(long = "population", default_value = "16")]
+        population: usize,
+
+        /// The number of games per pairing and color.
+        #[structopt(long = "games", default_value = "10")]
+        games: u32,
+
+        /// The standard deviation of the random changes to the weights.
+        #[structopt(long = "mutation", default_value = "0.3")]
+        mutation: f64,
+
+        /// Seed for all random decisions. A random seed is used if not
+        /// specified.
+        #[structopt(long = "seed")]
+        seed: Option<u64>,
+    },
 }
 
-impl PlayerInput {
-    /// Returns the player or `None` if it's a human player.
-    fn computer_player(self) -> Option<Box<dyn Player>> {
+impl Command {
+    fn run(self) {
         match self {
-            PlayerInput::Human => None,
-            PlayerInput::Other(p) => Some(p),
-        }
-    }
+            Command::Analyze { position } => {
+                if let Some(winner) = position.winner() {
+                    fail(format!("The game is already over: {:?} won", winner));
+                }
+
+                println!("Position: {}", notation::position_to_string(&position));
+                print_analysis(&position, position.to_move());
+            }
+
+            Command::Perft { depth, position } => {
+                let start = Instant::now();
+                let result = perft(&position, depth);
+                let elapsed = start.elapsed();
+
+                println!("Position: {}", notation::position_to_string(&position));
+                for (edge, nodes) in &result.per_move {
+                    println!("  {}: {}", edge, nodes);
+                }
+                println!("Nodes: {}", result.nodes);
+                for (ply, ends) in result.game_ends.iter().enumerate() {
+                    println!("Games ending at ply {:>2}: {}", ply + 1, ends);
+                }
+                println!(
+                    "Time: {:.2?} ({:.0} nodes/s)",
+                    elapsed,
+                    result.nodes as f64 / elapsed.as_secs_f64(),
+                );
+            }
+
+            Command::Match { player_red, player_blue, games, time, seed } => {
+                let seed = seed.unwrap_or_else(|| thread_rng().gen());
+                println!("Using seed {}", seed);
+                let (seed_red, seed_blue) = derive_seeds(seed);
+                let red = player_red.create(EdgeState::Red, seed_red, false);
+                let blue = player_blue.create(EdgeState::Blue, seed_blue, false);
+                let (mut red, mut blue) = match (red, blue) {
+                    (Some(red), Some(blue)) => (red, blue),
+                    _ => fail("Human players can only play in the GUI"),
+                };
+
+                let results = play_match(&mut *red, &mut *blue, games, time);
+
+                let wins = |color| results.iter().filter(|r| r.winner == color).count();
+                let average_length = results.iter().map(|r| r.moves.len()).sum::<usize>() as f64
+                    / results.len().max(1) as f64;
+                println!("Red ({}) won {} games", player_red, wins(EdgeState::Red));
+                println!("Blue ({}) won {} games", player_blue, wins(EdgeState::Blue));
+                println!("Average game length: {:.1} moves", average_length);
+                let forfeits = |reason| {
+                    results.iter().filter(|r| r.forfeit == Some(reason)).count()
+                };
+                if time.is_some() {
+                    println!("Games lost on time: {}", forfeits(Forfeit::Time));
+                }
+                let invalid = forfeits(Forfeit::InvalidMove);
+                if invalid > 0 {
+                    println!("Games lost by invalid moves: {}", invalid);
+                }
+            }
+
+            Command::Tournament { players, games, time, seed, ratings: ratings_path } => {
+                if players.contains(&PlayerConfig::Human) {
+                    fail("Human players can only play in the GUI");
+                }
+
+                let seed = seed.unwrap_or_else(|| thread_rng().gen());
+                println!("Using seed {}", seed);
+                let mut rng = seeded_rng(seed);
+                let mut ratings = Ratings::load(&ratings_path).unwrap_or_else(|e| fail(e));
+
+                for red_config in &players {
+                    for blue_config in &players {
+                        if red_config == blue_config {
+                            continue;
+                        }
+
+                        let (seed_red, seed_blue) = derive_seeds(rng.gen());
+                        let mut red = red_config.create(EdgeState::Red, seed_red, false).unwrap();
+                        let mut blue = blue_config.create(EdgeState::Blue, seed_blue, false)
+                            .unwrap();
+                        let results = play_match(&mut *red, &mut *blue, games, time);
 
-    fn from_str(input: &str, color: EdgeState) -> Result<Self, String> {
-        match input {
-            "human" => Ok(PlayerInput::Human),
-            "random" => Ok(PlayerInput::Other(Box::new(Random::new(color)))),
-            "dumb_random" => Ok(PlayerInput::Other(Box::new(DumbRandom::new(color)))),
-            "minimax" => Ok(PlayerInput::Other(Box::new(MiniMax::new(color)))),
-            _ => Err(format!(
-                "invalid player '{}' (valid options: 'human', 'random', 'dumb_random')",
-                input,
-            )),
+                        let red_name = red_config.to_string();
+                        let blue_name = blue_config.to_string();
+                        let red_wins = results.iter()
+                            .filter(|r| r.winner == EdgeState::Red)
+                            .count();
+                        println!(
+                            "{} vs {}: {} - {}",
+                            red_name,
+                            blue_name,
+                            red_wins,
+                            results.len() - red_wins,
+                        );
+                        for result in &results {
+                            ratings.record(&red_name, &blue_name, result);
+                        }
+                    }
+                }
+
+                ratings.save(&ratings_path).unwrap_or_else(|e| fail(e));
+
+                println!();
+                println!("Leaderboard (stored in '{}'):", ratings_path.display());
+                println!("   #  {:>6}  {:>6}  {:>6}  player", "elo", "games", "wins");
+                for (i, (player, rating)) in ratings.leaderboard().iter().enumerate() {
+                    println!(
+                        "  {:>2}  {:>6.0}  {:>6}  {:>5.1}%  {}",
+                        i + 1,
+                        rating.elo,
+                        rating.games,
+                        100.0 * rating.wins as f64 / rating.games.max(1) as f64,
+                        player,
+                    );
+                }
+            }
+
+            Command::Book { output, plies } => {
+                let start = Instant::now();
+                let book = OpeningBook::generate(plies);
+                book.save(&output).unwrap_or_else(|e| fail(e));
+                println!(
+                    "Wrote {} positions to '{}' in {:.2?}",
+                    book.len(),
+                    output.display(),
+                    start.elapsed(),
+                );
+            }
+
+            Command::Train { output, games, learning_rate, exploration, seed } => {
+                if !(0.0..=1.0).contains(&exploration) {
+                    fail(format!("exploration must be between 0 and 1 (is {})", exploration));
+                }
+
+                let table = if output.exists() {
+                    let table = ValueTable::load(&output).unwrap_or_else(|e| fail(e));
+                    println!(
+                        "Continuing with {} positions from '{}'",
+                        table.len(),
+                        output.display(),
+                    );
+                    table
+                } else {
+                    ValueTable::new()
+                };
+
+                let seed = seed.unwrap_or_else(|| thread_rng().gen());
+                println!("Using seed {}", seed);
+                let config = TrainConfig { learning_rate, exploration, seed };
+                let start = Instant::now();
+                let report_every = (games / 10).max(1);
+                let table = learning::train(table, games, config, |game, table| {
+                    if game % report_every == 0 {
+                        println!(
+                            "{:>8} games, {:>7} positions, {:.1?}",
+                            game,
+                            table.len(),
+                            start.elapsed(),
+                        );
+                    }
+                });
+
+                table.save(&output).unwrap_or_else(|e| fail(e));
+                println!("Wrote {} positions to '{}'", table.len(), output.display());
+            }
+
+            Command::Render { position, last_move, size, theme, output } => {
+                check_image_size(size);
+                let (state, moves) = notation::parse_position_with_moves(&position)
+                    .unwrap_or_else(|e| fail(e));
+                let highlights = Highlights {
+                    last_move: last_move.or_else(|| moves.last().cloned()),
+                    triangle: state.winner().and_then(|w| state.find_triangle(w.opponent())),
+                };
+
+                Scene::board(&state, size, &highlights, &theme)
+                    .save(&output)
+                    .unwrap_or_else(|e| fail(e));
+                println!("Wrote '{}'", output.display());
+            }
+
+            Command::RenderGame { game, size, delay, theme, output } => {
+                let (_, moves) = notation::parse_position_with_moves(&game)
+                    .unwrap_or_else(|e| fail(e));
+                if moves.is_empty() {
+                    fail(format!("'{}' contains no moves", game));
+                }
+                check_image_size(size);
+                let scenes = render::game_scenes(&moves, size, &theme)
+                    .unwrap_or_else(|e| fail(e));
+
+                if output.extension().and_then(|e| e.to_str()) == Some("gif") {
+                    let delay = (delay / 10).min(u16::MAX as u32) as u16;
+                    render::save_gif(&scenes, &output, delay).unwrap_or_else(|e| fail(e));
+                    println!("Wrote {} frames to '{}'", scenes.len(), output.display());
+                } else {
+                    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+                    let extension = output.extension().unwrap_or_default().to_string_lossy();
+                    let digits = (scenes.len() - 1).to_string().len().max(2);
+                    for (i, scene) in scenes.iter().enumerate() {
+                        let path = output.with_file_name(
+                            format!("{}-{:0width$}.{}", stem, i, extension, width = digits),
+                        );
+                        scene.save(&path).unwrap_or_else(|e| fail(e));
+                    }
+                    println!("Wrote {} images next to '{}'", scenes.len(), output.display());
+                }
+            }
+
+            Command::Evolve { output, generations, population, games, mutation, seed } => {
+                if population < 2 {
+                    fail("The population must contain at least 2 candidates");
+                }
+                if !(mutation.is_finite() && mutation >= 0.0) {
+                    fail(format!("mutation must be a non-negative number (is {})", mutation));
+                }
+
+                let start = if output.exists() {
+                    let weights = Weights::load(&output).unwrap_or_else(|e| fail(e));
+                    println!("Starting with the weights from '{}'", output.display());
+                    weights
+                } else {
+                    Weights::default()
+                };
+
+                let seed = seed.unwrap_or_else(|| thread_rng().gen());
+                println!("Using seed {}", seed);
+                let config = EvolveConfig { population, generations, games, mutation, seed };
+                let best = evolution::evolve(start, config, |generation| {
+                    let weights = generation.best.0.iter()
+                        .map(|w| format!("{:>6.2}", w))
+                        .collect::<Vec<_>>();
+                    println!(
+                        "Generation {:>3}: best {:.3}, mean {:.3}, best weights [{}]",
+                        generation.number,
+                        generation.best_fitness,
+                        generation.mean_fitness,
+                        weights.join(" "),
+                    );
+                });
+
+                best.save(&output).unwrap_or_else(|e| fail(e));
+                println!("Wrote the best weights to '{}':", output.display());
+                print!("{}", best);
+            }
         }
     }
 }
 
-fn parse_player_red(input: &str) -> Result<PlayerInput, String> {
-    PlayerInput::from_str(input, EdgeState::Red)
+/// Prints `msg` to stderr and exits with an error code.
+fn fail(msg: impl Display) -> ! {
+    eprintln!("{}", msg);
+    process::exit(1);
 }
 
-fn parse_player_blue(input: &str) -> Result<PlayerInput, String> {
-    PlayerInput::from_str(input, EdgeState::Blue)
+/// Exits with an error if `size` is not a valid size for rendered images.
+fn check_image_size(size: u32) {
+    if !(1..=render::MAX_IMAGE_SIZE).contains(&size) {
+        fail(format!("size must be between 1 and {} (is {})", render::MAX_IMAGE_SIZE, size));
+    }
 }
 
-#[derive(StructOpt)]
-#[structopt(
-    name = "sim",
-    about = "Implementation of the Sim pencil game",
-    usage = "sim [FLAGS] <player_red> <player_blue>",
-)]
-struct Opt {
-    /// The player with color red (the starting player). Possible values:
-    /// 'human', 'random', 'random_dumb'.
-    #[structopt(default_value = "human", parse(try_from_str = "parse_player_red"))]
-    player_red: PlayerInput,
-
-    /// The player with color blue.
-    #[structopt(default_value = "random", parse(try_from_str = "parse_player_blue"))]
-    player_blue: PlayerInput,
+fn parse_position(input: &str) -> Result<GameState, String> {
+    notation::parse_position(input)
 }
@@ -0,0 +1,199 @@
+//! Textual representations of moves, positions and whole games.
+//!
+//! - An edge is written as its two endpoints, e.g. `0-3` (`03` is accepted
+//!   as well).
+//! - A position is a string of 15 characters, one per edge (in order of the
+//!   edge IDs): `.` for uncolored, `r` for red and `b` for blue edges.
+//! - A position from the view of one player is written like a position, but
+//!   with `x` for the edges of that player and `o` for the edges of the
+//!   opponent. It's given as two edge masks: the player's and the opponent's.
+//! - A move list is a sequence of edges separated by whitespace or commas.
+//!   Red makes the first move.
+//! - A game file contains lines of the form `key: value`. The moves of the
+//!   game are stored under the key `moves`, the player types under `red` and
+//!   `blue` and the random seed under `seed` (a data file, see `io`).
+
+use std::path::Path;
+
+use crate::{
+    game::{GameState, Edge, EdgeState, Vertex},
+    io::{self, DataFile},
+};
+
+
+/// Parses an edge written as its two endpoints (`0-3` or `03`).
+pub fn parse_edge(input: &str) -> Result<Edge, String> {
+    let digits = input.chars().filter(|&c| c != '-').collect::<Vec<_>>();
+    let vertex = |c: char| {
+        c.to_digit(10)
+            .and_then(|d| Vertex::try_new(d as u8).ok())
+            .ok_or_else(|| format!("invalid vertex '{}' in edge '{}'", c, input))
+    };
+
+    match digits[..] {
+        [a, b] => {
+            Edge::try_between(vertex(a)?, vertex(b)?)
+                .map_err(|e| format!("invalid edge '{}': {}", input, e))
+        }
+        _ => Err(format!("invalid edge '{}' (expected two vertices, e.g. '0-3')", input)),
+    }
+}
+
+/// Writes the position given by the masks of both players from the view of
+/// the player owning `own` (see module documentation).
+pub fn view_to_string(own: u16, other: u16) -> String {
+    Edge::all_edges()
+        .map(|edge| match () {
+            _ if own & edge.bit() != 0 => 'x',
+            _ if other & edge.bit() != 0 => 'o',
+            _ => '.',
+        })
+        .collect()
+}
+
+/// Parses a position from the view of one player (see module
+/// documentation) and returns the masks of the player and the opponent.
+pub fn parse_view(input: &str) -> Result<(u16, u16), String> {
+    if input.chars().count() != 15 {
+        return Err(format!("invalid position '{}' (expected 15 characters)", input));
+    }
+
+    let mut out = (0, 0);
+    for (edge, c) in Edge::all_edges().zip(input.chars()) {
+        match c {
+            'x' => out.0 |= edge.bit(),
+            'o' => out.1 |= edge.bit(),
+            '.' => {}
+            _ => return Err(format!("invalid character '{}' in position '{}'", c, input)),
+        }
+    }
+
+    Ok(out)
+}
+
+/// Parses a list of moves (see module documentation).
+pub fn parse_move_list(input: &str) -> Result<Vec<Edge>, String> {
+    input.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(parse_edge)
+        .collect()
+}
+
+/// Returns the position string of `state` (see module documentation).
+pub fn position_to_string(state: &GameState) -> String {
+    Edge::all_edges()
+        .map(|e| match state.edge_state(e) {
+            EdgeState::None => '.',
+            EdgeState::Red => 'r',
+            EdgeState::Blue => 'b',
+        })
+        .collect()
+}
+
+/// Parses a position string (see module documentation). Red is assumed to
+/// have made the first move.
+pub fn parse_position_string(input: &str) -> Result<GameState, String> {
+    if input.chars().count() != 15 {
+        return Err(format!("invalid position '{}' (expected 15 characters)", input));
+    }
+
+    let mut colors = [EdgeState::None; 15];
+    for (color, c) in colors.iter_mut().zip(input.chars()) {
+        *color = match c {
+            '.' => EdgeState::None,
+            'r' | 'R' => EdgeState::Red,
+            'b' | 'B' => EdgeState::Blue,
+            _ => return Err(format!("invalid character '{}' in position '{}'", c, input)),
+        };
+    }
+
+    GameState::from_edges(colors, EdgeState::Red)
+        .map_err(|e| format!("invalid position '{}': {}", input, e))
+}
+
+/// Plays all given moves, starting with red. Returns an error if an edge is
+/// colored twice or if a move is made after the game has ended.
+pub fn play_moves(moves: &[Edge]) -> Result<GameState, String> {
+    let mut state = GameState::new();
+    for (i, &edge) in moves.iter().enumerate() {
+        state.play(edge).map_err(|e| format!("invalid move {} ({}): {}", i + 1, edge, e))?;
+    }
+
+    Ok(state)
+}
+
+/// A recorded game as stored in a game file (see module documentation).
+#[derive(Clone, Debug, Default)]
+pub struct GameRecord {
+    /// The player types of red and blue (e.g. `minimax`).
+    pub red: Option<String>,
+    pub blue: Option<String>,
+
+    /// The seed used to derive the randomness of all players.
+    pub seed: Option<u64>,
+
+    pub moves: Vec<Edge>,
+}
+
+impl GameRecord {
+    /// Reads a game file. Unknown keys are ignored.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file = DataFile::read(path)?;
+        let mut out = Self::default();
+        for line in file.lines() {
+            let (key, value) = line.key_value()?;
+            match key {
+                "red" => out.red = Some(value.to_string()),
+                "blue" => out.blue = Some(value.to_string()),
+                "seed" => {
+                    let seed = value.parse()
+                        .map_err(|_| line.error(format_args!("invalid seed '{}'", value)))?;
+                    out.seed = Some(seed);
+                }
+                "moves" => out.moves.extend(parse_move_list(value).map_err(|e| line.error(e))?),
+                _ => {}
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Writes this record as game file to `path`.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let mut out = String::from("# Sim game record\n");
+        if let Some(red) = &self.red {
+            out += &format!("red: {}\n", red);
+        }
+        if let Some(blue) = &self.blue {
+            out += &format!("blue: {}\n", blue);
+        }
+        if let Some(seed) = self.seed {
+            out += &format!("seed: {}\n", seed);
+        }
+        let moves = self.moves.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        out += &format!("moves: {}\n", moves.join(" "));
+
+        io::write(path, &out)
+    }
+}
+
+/// Parses a position given as position string, move list or path to a game
+/// file.
+pub fn parse_position(input: &str) -> Result<GameState, String> {
+    parse_position_with_moves(input).map(|(state, _)| state)
+}
+
+/// Like `parse_position`, but also returns the moves leading to the position
+/// (empty for position strings, which don't contain the order of moves).
+pub fn parse_position_with_moves(input: &str) -> Result<(GameState, Vec<Edge>), String> {
+    let path = Path::new(input);
+    let moves = if path.is_file() {
+        GameRecord::read(path)?.moves
+    } else if input.len() == 15 && input.chars().all(|c| ".rRbB".contains(c)) {
+        return Ok((parse_position_string(input)?, vec![]));
+    } else {
+        parse_move_list(input)?
+    };
+
+    Ok((play_moves(&moves)?, moves))
+}
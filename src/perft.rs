@@ -0,0 +1,91 @@
+//! Perft-style counting of move sequences.
+//!
+//! Counting all possible move sequences up to a given depth is a simple way
+//! to check that move generation and game end detection work correctly: the
+//! numbers for a position must never change, no matter how `GameState` is
+//! implemented internally.
+
+use crate::game::{GameState, Edge, Outcome};
+
+
+/// The result of `perft`.
+#[derive(Clone, Debug)]
+pub struct PerftResult {
+    /// The number of move sequences of length `depth` plus the number of
+    /// shorter sequences that ended the game.
+    pub nodes: u64,
+
+    /// The number of sequences starting with each legal move.
+    pub per_move: Vec<(Edge, u64)>,
+
+    /// `game_ends[i]` is the number of sequences where the game ends with the
+    /// `i + 1`-th move.
+    pub game_ends: Vec<u64>,
+}
+
+/// Counts all legal move sequences of length `depth` starting from `state`.
+/// Sequences stop early if a move completes a triangle.
+pub fn perft(state: &GameState, depth: u8) -> PerftResult {
+    let mut game_ends = vec![0; depth as usize];
+    let per_move = if depth == 0 {
+        vec![]
+    } else {
+        state.legal_moves()
+            .map(|edge| (edge, count(state, edge, depth, &mut game_ends)))
+            .collect::<Vec<_>>()
+    };
+
+    PerftResult {
+        nodes: if depth == 0 { 1 } else { per_move.iter().map(|(_, n)| n).sum() },
+        per_move,
+        game_ends,
+    }
+}
+
+/// Plays `edge` in `state` and counts all sequences with `depth - 1` further
+/// moves.
+fn count(state: &GameState, edge: Edge, depth: u8, game_ends: &mut [u64]) -> u64 {
+    let mut next = state.clone();
+    match next.play(edge).expect("legal move was rejected") {
+        Outcome::Won(_) => {
+            // `game_ends` has one entry per ply, so the ply of this move is
+            // given by how much depth is left.
+            let len = game_ends.len();
+            game_ends[len - depth as usize] += 1;
+            1
+        }
+        Outcome::Ongoing if depth == 1 => 1,
+        Outcome::Ongoing => {
+            next.legal_moves()
+                .map(|e| count(&next, e, depth - 1, game_ends))
+                .sum()
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_board() {
+        let expected = [1, 15, 210, 2730, 32760, 360360];
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&GameState::new(), depth as u8).nodes, nodes);
+        }
+    }
+
+    #[test]
+    fn breakdown() {
+        let result = perft(&GameState::new(), 5);
+
+        // All first moves are equivalent by symmetry.
+        assert_eq!(result.per_move.len(), 15);
+        assert!(result.per_move.iter().all(|&(_, n)| n == 360360 / 15));
+
+        // Red completes a triangle with its third move at the earliest.
+        assert_eq!(result.game_ends[..4], [0; 4]);
+        assert!(result.game_ends[4] > 0);
+    }
+}
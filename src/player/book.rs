@@ -0,0 +1,59 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::book::OpeningBook;
+use crate::game::{Edge, EdgeState, GameState, SimError};
+use super::{Player, MiniMax};
+
+/// Plays moves from an opening book while the position is in the book and
+/// lets another player choose the move otherwise.
+pub struct Book {
+    color: EdgeState,
+    book: Arc<OpeningBook>,
+    fallback: Box<dyn Player>,
+}
+
+impl Book {
+    /// Creates a player that uses `book` and asks `fallback` for moves that
+    /// are not in the book.
+    pub fn with_book(color: EdgeState, book: Arc<OpeningBook>, fallback: Box<dyn Player>) -> Self {
+        Self { color, book, fallback }
+    }
+}
+
+impl Player for Book {
+    /// Creates a player with an empty book that always lets `MiniMax` choose
+    /// the move. Use `with_book` to pass a book.
+    fn new(color: EdgeState, seed: u64) -> Self
+    where
+        Self: Sized
+    {
+        Self::with_book(color, Arc::new(OpeningBook::new()), Box::new(MiniMax::new(color, seed)))
+    }
+
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
+        match self.book.lookup(state, self.color) {
+            Some(entry) if state.edge_state(entry.edge).is_none() => Ok(entry.edge),
+            _ => self.fallback.next_move(state),
+        }
+    }
+
+    fn set_time_budget(&mut self, budget: Duration) {
+        self.fallback.set_time_budget(budget);
+    }
+
+    fn on_game_start(&mut self, state: &GameState) {
+        self.fallback.on_game_start(state);
+    }
+
+    fn on_move(&mut self, edge: Edge, color: EdgeState) {
+        self.fallback.on_move(edge, color);
+    }
+
+    fn on_game_end(&mut self, winner: EdgeState) {
+        self.fallback.on_game_end(winner);
+    }
+
+    fn reset(&mut self) {
+        self.fallback.reset();
+    }
+}
@@ -0,0 +1,559 @@
+//! Choosing and configuring players by name, e.g. on the command line.
+//!
+//! A player is given by its name, optionally followed by a colon and a comma
+//! separated list of options: `minimax:randomize=false,verbose=true`.
+
+use std::{fmt, path::PathBuf, str::FromStr, sync::{Arc, Mutex}};
+
+use crate::{book::OpeningBook, game::EdgeState, heuristic::Weights, learning::ValueTable};
+use super::{Player, Book, DumbRandom, Heuristic, Learned, Random, MiniMax};
+
+
+/// Description of all players and their options, suitable for `--help`.
+pub const PLAYER_HELP: &str = "\
+PLAYERS:
+    Players are given as 'name' or 'name:option=value,option=value'.
+
+    human          Moves are chosen by clicking in the GUI.
+    random         Chooses a random move that doesn't lose immediately (if
+                   possible).
+        seed=N         Seed for this player (overrides --seed)
+    dumb_random    Chooses a completely random move.
+        seed=N         Seed for this player (overrides --seed)
+    minimax        Searches the game tree for a winning move.
+        randomize=B    Choose a random move if no winning move is known
+                       (default: true). Otherwise the first safe move is used.
+        verbose=B      Print what the player is thinking
+        seed=N         Seed for this player (overrides --seed)
+    book           Plays moves from an opening book (see 'sim book') and lets
+                   another player choose moves that are not in the book.
+        file=PATH      The book file (required)
+        then=PLAYER    The player used outside of the book, including its
+                       options (default: minimax). Must be the last option.
+    learned        Plays the move with the highest learned value (see 'sim
+                   train').
+        file=PATH      The value table (required)
+        exploration=P  Probability of playing a random move (default: 0)
+        seed=N         Seed for this player (overrides --seed)
+    heuristic      Plays the move with the best heuristic evaluation.
+        file=PATH      Weights file (see 'sim evolve'; default: built-in
+                       weights)
+        seed=N         Seed for this player (overrides --seed)";
+
+/// A player together with its settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlayerConfig {
+    Human,
+    Random(RandomConfig),
+    DumbRandom(RandomConfig),
+    MiniMax(MiniMaxConfig),
+    Book(BookConfig),
+    Learned(LearnedConfig),
+    Heuristic(HeuristicConfig),
+}
+
+/// Settings of the `random` and `dumb_random` players.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RandomConfig {
+    /// Overrides the seed derived from the game seed.
+    pub seed: Option<u64>,
+}
+
+/// Settings of the `minimax` player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MiniMaxConfig {
+    /// Overrides the seed derived from the game seed.
+    pub seed: Option<u64>,
+
+    /// Whether to choose a random move when no winning move is known.
+    pub randomize: bool,
+
+    /// Whether to print what the player is thinking. If `None`, the caller
+    /// decides.
+    pub verbose: Option<bool>,
+}
+
+/// Settings of the `book` player.
+#[derive(Clone, Debug)]
+pub struct BookConfig {
+    /// The path of the book file.
+    pub file: PathBuf,
+
+    /// The book loaded from `file`.
+    pub book: Arc<OpeningBook>,
+
+    /// The player used for positions that are not in the book.
+    pub then: Box<PlayerConfig>,
+}
+
+/// Two book configs are equal if they use the same file and fallback player.
+impl PartialEq for BookConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.file == other.file && self.then == other.then
+    }
+}
+
+impl Eq for BookConfig {}
+
+/// Settings of the `learned` player.
+#[derive(Clone, Debug)]
+pub struct LearnedConfig {
+    /// The path of the table file.
+    pub file: PathBuf,
+
+    /// The table loaded from `file`.
+    pub table: Arc<Mutex<ValueTable>>,
+
+    /// The probability of playing a random move.
+    pub exploration: f64,
+
+    /// Overrides the seed derived from the game seed.
+    pub seed: Option<u64>,
+}
+
+/// Two learned configs are equal if they use the same file and settings.
+impl PartialEq for LearnedConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.file == other.file
+            && self.exploration == other.exploration
+            && self.seed == other.seed
+    }
+}
+
+impl Eq for LearnedConfig {}
+
+/// Settings of the `heuristic` player.
+#[derive(Clone, Debug, Default)]
+pub struct HeuristicConfig {
+    /// The path of the weights file, if any.
+    pub file: Option<PathBuf>,
+
+    /// The weights loaded from `file` (or the default weights).
+    pub weights: Weights,
+
+    /// Overrides the seed derived from the game seed.
+    pub seed: Option<u64>,
+}
+
+/// Two heuristic configs are equal if they use the same file and seed.
+impl PartialEq for HeuristicConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.file == other.file && self.seed == other.seed
+    }
+}
+
+impl Eq for HeuristicConfig {}
+
+impl Default for MiniMaxConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            randomize: true,
+            verbose: None,
+        }
+    }
+}
+
+impl PlayerConfig {
+    /// The name of the player (without options).
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerConfig::Human => "human",
+            PlayerConfig::Random(_) => "random",
+            PlayerConfig::DumbRandom(_) => "dumb_random",
+            PlayerConfig::MiniMax(_) => "minimax",
+            PlayerConfig::Book(_) => "book",
+            PlayerConfig::Learned(_) => "learned",
+            PlayerConfig::Heuristic(_) => "heuristic",
+        }
+    }
+
+    /// Creates the player or returns `None` if it's a human player. `seed` is
+    /// used unless the config specifies its own seed. `verbose` is used for
+    /// players that can print their thoughts unless the config says
+    /// otherwise.
+    pub fn create(&self, color: EdgeState, seed: u64, verbose: bool) -> Option<Box<dyn Player>> {
+        match self {
+            PlayerConfig::Human => None,
+            PlayerConfig::Random(config) => {
+                Some(Box::new(Random::new(color, config.seed.unwrap_or(seed))))
+            }
+            PlayerConfig::DumbRandom(config) => {
+                Some(Box::new(DumbRandom::new(color, config.seed.unwrap_or(seed))))
+            }
+            PlayerConfig::MiniMax(config) => {
+                let mut player = MiniMax::new(color, config.seed.unwrap_or(seed));
+                player.set_verbose(config.verbose.unwrap_or(verbose));
+                player.set_randomize(config.randomize);
+                Some(Box::new(player))
+            }
+            PlayerConfig::Book(config) => {
+                let fallback = config.then.create(color, seed, verbose)?;
+                Some(Box::new(Book::with_book(color, config.book.clone(), fallback)))
+            }
+            PlayerConfig::Learned(config) => {
+                let seed = config.seed.unwrap_or(seed);
+                let mut player = Learned::with_table(color, seed, config.table.clone());
+                player.set_exploration(config.exploration);
+                Some(Box::new(player))
+            }
+            PlayerConfig::Heuristic(config) => {
+                let seed = config.seed.unwrap_or(seed);
+                Some(Box::new(Heuristic::with_weights(color, seed, config.weights)))
+            }
+        }
+    }
+}
+
+impl FromStr for PlayerConfig {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (name, mut options) = match input.split_once(':') {
+            Some((name, options)) => (name.trim(), options),
+            None => (input.trim(), ""),
+        };
+
+        // The value of `then` is a player with its own options, so it's
+        // always the last option and extends to the end of the input.
+        let mut then = None;
+        if let Some(pos) = options.find("then=") {
+            let before = options[..pos].trim_end();
+            if pos == 0 || before.ends_with(',') {
+                then = Some(options[pos + "then=".len()..].trim());
+                options = before.strip_suffix(',').unwrap_or(before);
+            }
+        }
+
+        let options = if options.trim().is_empty() {
+            vec![]
+        } else {
+            options.split(',')
+                .map(|option| {
+                    let option = option.trim();
+                    let key_value = option.split_once('=')
+                        .map(|(key, value)| (key.trim(), value.trim()));
+                    match key_value {
+                        Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+                            Ok((key, value))
+                        }
+                        _ => Err(format!(
+                            "invalid option '{}' in '{}' (expected 'key=value')",
+                            option,
+                            input,
+                        )),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let unknown = |key: &str| {
+            format!("unknown option '{}' for player '{}' (see --help)", key, name)
+        };
+        if then.is_some() && name != "book" {
+            return Err(unknown("then"));
+        }
+
+        match name {
+            "human" => match options.first() {
+                Some((key, _)) => Err(unknown(key)),
+                None => Ok(PlayerConfig::Human),
+            },
+            "random" | "dumb_random" => {
+                let mut config = RandomConfig::default();
+                for (key, value) in options {
+                    match key {
+                        "seed" => config.seed = Some(parse_value(key, value)?),
+                        _ => return Err(unknown(key)),
+                    }
+                }
+
+                if name == "random" {
+                    Ok(PlayerConfig::Random(config))
+                } else {
+                    Ok(PlayerConfig::DumbRandom(config))
+                }
+            }
+            "minimax" => {
+                let mut config = MiniMaxConfig::default();
+                for (key, value) in options {
+                    match key {
+                        "seed" => config.seed = Some(parse_value(key, value)?),
+                        "randomize" => config.randomize = parse_value(key, value)?,
+                        "verbose" => config.verbose = Some(parse_value(key, value)?),
+                        _ => return Err(unknown(key)),
+                    }
+                }
+                Ok(PlayerConfig::MiniMax(config))
+            }
+            "book" => {
+                let mut file = None;
+                for (key, value) in options {
+                    match key {
+                        "file" => file = Some(PathBuf::from(value)),
+                        _ => return Err(unknown(key)),
+                    }
+                }
+
+                let file = file.ok_or("player 'book' requires the option 'file'")?;
+                let then = then.unwrap_or("minimax").parse::<PlayerConfig>()?;
+                if then == PlayerConfig::Human {
+                    return Err("player 'book' can't be combined with a human player".into());
+                }
+
+                Ok(PlayerConfig::Book(BookConfig {
+                    book: Arc::new(OpeningBook::load(&file)?),
+                    file,
+                    then: Box::new(then),
+                }))
+            }
+            "learned" => {
+                let mut file = None;
+                let mut exploration = 0.0;
+                let mut seed = None;
+                for (key, value) in options {
+                    match key {
+                        "file" => file = Some(PathBuf::from(value)),
+                        "exploration" => exploration = parse_value(key, value)?,
+                        "seed" => seed = Some(parse_value(key, value)?),
+                        _ => return Err(unknown(key)),
+                    }
+                }
+
+                if !(0.0..=1.0).contains(&exploration) {
+                    return Err(format!("exploration must be between 0 and 1 (is {})", exploration));
+                }
+                let file = file.ok_or("player 'learned' requires the option 'file'")?;
+                Ok(PlayerConfig::Learned(LearnedConfig {
+                    table: Arc::new(Mutex::new(ValueTable::load(&file)?)),
+                    file,
+                    exploration,
+                    seed,
+                }))
+            }
+            "heuristic" => {
+                let mut config = HeuristicConfig::default();
+                for (key, value) in options {
+                    match key {
+                        "file" => config.file = Some(PathBuf::from(value)),
+                        "seed" => config.seed = Some(parse_value(key, value)?),
+                        _ => return Err(unknown(key)),
+                    }
+                }
+
+                if let Some(file) = &config.file {
+                    config.weights = Weights::load(file)?;
+                }
+                Ok(PlayerConfig::Heuristic(config))
+            }
+            _ => Err(format!(
+                "invalid player '{}' (valid options: 'human', 'random', 'dumb_random', \
+                    'minimax', 'book', 'learned', 'heuristic')",
+                name,
+            )),
+        }
+    }
+}
+
+/// Writes the player in the format accepted by `from_str`. Options that have
+/// their default value are omitted.
+impl fmt::Display for PlayerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut options = Vec::new();
+        match self {
+            PlayerConfig::Human => {}
+            PlayerConfig::Random(config) | PlayerConfig::DumbRandom(config) => {
+                if let Some(seed) = config.seed {
+                    options.push(format!("seed={}", seed));
+                }
+            }
+            PlayerConfig::MiniMax(config) => {
+                if !config.randomize {
+                    options.push("randomize=false".to_string());
+                }
+                if let Some(verbose) = config.verbose {
+                    options.push(format!("verbose={}", verbose));
+                }
+                if let Some(seed) = config.seed {
+                    options.push(format!("seed={}", seed));
+                }
+            }
+            PlayerConfig::Book(config) => {
+                options.push(format!("file={}", config.file.display()));
+                options.push(format!("then={}", config.then));
+            }
+            PlayerConfig::Heuristic(config) => {
+                if let Some(file) = &config.file {
+                    options.push(format!("file={}", file.display()));
+                }
+                if let Some(seed) = config.seed {
+                    options.push(format!("seed={}", seed));
+                }
+            }
+            PlayerConfig::Learned(config) => {
+                options.push(format!("file={}", config.file.display()));
+                if config.exploration != 0.0 {
+                    options.push(format!("exploration={}", config.exploration));
+                }
+                if let Some(seed) = config.seed {
+                    options.push(format!("seed={}", seed));
+                }
+            }
+        }
+
+        write!(f, "{}", self.name())?;
+        if !options.is_empty() {
+            write!(f, ":{}", options.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value '{}' for option '{}'", value, key))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    /// Writes an empty data file that can be used as book, table or weights
+    /// file and returns its path.
+    fn empty_file(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("sim-config-{}-{}", std::process::id(), name));
+        fs::write(&path, "").unwrap();
+        path
+    }
+
+    fn parse(input: &str) -> Result<PlayerConfig, String> {
+        input.parse()
+    }
+
+    fn assert_round_trip(config: &PlayerConfig) {
+        let written = config.to_string();
+        assert_eq!(&parse(&written).unwrap(), config, "{}", written);
+    }
+
+    #[test]
+    fn round_trip() {
+        let book = empty_file("round-trip.book");
+        let table = empty_file("round-trip.table");
+        let weights = empty_file("round-trip.weights");
+
+        let mut inputs = vec![
+            "human".to_string(),
+            "random".to_string(),
+            "random:seed=3".to_string(),
+            "dumb_random:seed=18446744073709551615".to_string(),
+            "minimax".to_string(),
+            "minimax:randomize=false,verbose=true,seed=7".to_string(),
+            "minimax:verbose=false".to_string(),
+            format!("learned:file={}", table.display()),
+            format!("learned:file={},exploration=0.25,seed=1", table.display()),
+            "heuristic".to_string(),
+            format!("heuristic:file={},seed=2", weights.display()),
+            format!("book:file={},then=minimax", book.display()),
+            format!("book:file={},then=random:seed=5", book.display()),
+        ];
+        inputs.push(format!(
+            "book:file={},then=book:file={},then=minimax:randomize=false,seed=9",
+            book.display(),
+            book.display(),
+        ));
+
+        for input in &inputs {
+            let config = parse(input).unwrap();
+            assert_eq!(&config.to_string(), input);
+            assert_round_trip(&config);
+        }
+
+        // Configs built in code, including options with their default value.
+        let configs = vec![
+            PlayerConfig::MiniMax(MiniMaxConfig { seed: Some(0), ..MiniMaxConfig::default() }),
+            PlayerConfig::Random(RandomConfig { seed: Some(0) }),
+            PlayerConfig::Heuristic(HeuristicConfig::default()),
+        ];
+        for config in &configs {
+            assert_round_trip(config);
+        }
+    }
+
+    #[test]
+    fn nested_options() {
+        let book = empty_file("nested.book");
+        let input = format!(
+            "book:file={}, then=book:file={},then=minimax:randomize=false,seed=9",
+            book.display(),
+            book.display(),
+        );
+        let outer = match parse(&input).unwrap() {
+            PlayerConfig::Book(config) => config,
+            other => panic!("unexpected player {}", other),
+        };
+        let inner = match *outer.then {
+            PlayerConfig::Book(config) => config,
+            other => panic!("unexpected player {}", other),
+        };
+        assert_eq!(*inner.then, PlayerConfig::MiniMax(MiniMaxConfig {
+            seed: Some(9),
+            randomize: false,
+            verbose: None,
+        }));
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let book = empty_file("unknown.book");
+        for input in &[
+            "human:seed=1".to_string(),
+            "random:depth=3".to_string(),
+            "minimax:file=x".to_string(),
+            "heuristic:exploration=0.5".to_string(),
+            "minimax:then=random".to_string(),
+            format!("book:file={},seed=1", book.display()),
+            format!("book:file={},then=minimax:depth=3", book.display()),
+        ] {
+            let err = parse(input).unwrap_err();
+            assert!(err.starts_with("unknown option"), "{}: {}", input, err);
+        }
+        assert!(parse("alphabeta").unwrap_err().starts_with("invalid player"));
+    }
+
+    #[test]
+    fn missing_values() {
+        let book = empty_file("missing.book");
+        for input in &[
+            "random:seed".to_string(),
+            "random:seed=".to_string(),
+            "minimax:=true".to_string(),
+            "heuristic:file=".to_string(),
+            format!("book:file={},then=", book.display()),
+        ] {
+            assert!(parse(input).is_err(), "{}", input);
+        }
+        assert!(parse("minimax:seed=x").unwrap_err().starts_with("invalid value"));
+        assert_eq!(
+            parse("book:then=random").unwrap_err(),
+            "player 'book' requires the option 'file'",
+        );
+    }
+
+    #[test]
+    fn trailing_and_empty_options() {
+        let book = empty_file("trailing.book");
+        for input in &[
+            "random:seed=1,".to_string(),
+            "minimax:randomize=false,,seed=1".to_string(),
+            "minimax:,seed=1".to_string(),
+            format!("book:file={},", book.display()),
+            format!("book:file={},,then=random", book.display()),
+            format!("book:file={},then=random:seed=1,", book.display()),
+        ] {
+            let err = parse(input).unwrap_err();
+            assert!(err.starts_with("invalid option"), "{}: {}", input, err);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use rand::seq::IteratorRandom;
+
+use crate::game::{Edge, EdgeState, GameState, SimError};
+use crate::heuristic::Weights;
+use super::{Player, SeededRng, seeded_rng};
+
+/// Plays the move with the best heuristic evaluation (see `heuristic`),
+/// looking only one move ahead. Moves completing a triangle are only played
+/// if there is no other move.
+pub struct Heuristic {
+    color: EdgeState,
+    weights: Weights,
+    rng: SeededRng,
+}
+
+impl Heuristic {
+    /// Creates a player using the given weights.
+    pub fn with_weights(color: EdgeState, seed: u64, weights: Weights) -> Self {
+        Self {
+            color,
+            weights,
+            rng: seeded_rng(seed),
+        }
+    }
+}
+
+impl Player for Heuristic {
+    /// Creates a player with the default weights.
+    fn new(color: EdgeState, seed: u64) -> Self
+    where
+        Self: Sized
+    {
+        Self::with_weights(color, seed, Weights::default())
+    }
+
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
+        let own = state.mask(self.color);
+        let other = state.mask(self.color.opponent());
+
+        // Ties are broken randomly.
+        let mut best = Vec::new();
+        let mut best_value = f64::NEG_INFINITY;
+        for edge in state.safe_moves(self.color) {
+            let value = self.weights.evaluate(own | edge.bit(), other);
+            if value > best_value {
+                best.clear();
+                best_value = value;
+            }
+            if value == best_value {
+                best.push(edge);
+            }
+        }
+
+        match best.into_iter().choose(&mut self.rng) {
+            Some(edge) => Ok(edge),
+            None => state.legal_moves().choose(&mut self.rng).ok_or(SimError::GameOver),
+        }
+    }
+}
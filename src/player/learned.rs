@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex};
+
+use rand::{Rng, seq::IteratorRandom};
+
+use crate::game::{Edge, EdgeState, GameState, SimError};
+use crate::learning::ValueTable;
+use super::{Player, SeededRng, seeded_rng};
+
+/// Plays the move leading to the position with the highest learned value
+/// (see `learning`). Can also update the values after each game, which is
+/// how the table is trained.
+pub struct Learned {
+    color: EdgeState,
+    rng: SeededRng,
+    table: Arc<Mutex<ValueTable>>,
+
+    /// The probability of playing a random move instead of the best one.
+    exploration: f64,
+
+    /// If set, the values of the positions after this player's moves are
+    /// updated at the end of each game.
+    learning_rate: Option<f32>,
+
+    /// The edges of this player and of the opponent in the current game.
+    own: u16,
+    other: u16,
+
+    /// The positions after each move of the current game, from the view of
+    /// the player who moved, and whether that player was us.
+    history: Vec<(u16, u16, bool)>,
+}
+
+impl Learned {
+    /// Creates a player using (and possibly training) `table`.
+    pub fn with_table(color: EdgeState, seed: u64, table: Arc<Mutex<ValueTable>>) -> Self {
+        Self {
+            color,
+            rng: seeded_rng(seed),
+            table,
+            exploration: 0.0,
+            learning_rate: None,
+            own: 0,
+            other: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Sets the probability of playing a random move instead of the best
+    /// known one (0 by default).
+    pub fn set_exploration(&mut self, exploration: f64) {
+        self.exploration = exploration;
+    }
+
+    /// Enables (`Some`) or disables (`None`) learning from the games played
+    /// (disabled by default).
+    pub fn set_learning_rate(&mut self, learning_rate: Option<f32>) {
+        self.learning_rate = learning_rate;
+    }
+
+    /// Updates the table with the positions of the game that just ended,
+    /// from the last move backwards.
+    fn learn(&mut self, winner: EdgeState, learning_rate: f32) {
+        let mut table = self.table.lock().unwrap();
+        let (&(own, other, ours), earlier) = match self.history.split_last() {
+            Some(last) => last,
+            None => return,
+        };
+
+        // The value of the position after the last move is given by the
+        // result. Usually, the last move completed a triangle, so the player
+        // who made it lost and the position doesn't need to be stored. But
+        // the game can also end because the other player ran out of time.
+        let mover = if ours { self.color } else { self.color.opponent() };
+        let mut next_value = if winner == mover { 1.0 } else { 0.0 };
+        if ours && winner == mover {
+            table.update(own, other, next_value, learning_rate);
+        }
+
+        for &(own, other, ours) in earlier.iter().rev() {
+            // The player who moved here wins iff the opponent loses from the
+            // next position on.
+            if ours {
+                table.update(own, other, 1.0 - next_value, learning_rate);
+            }
+            next_value = table.value(own, other);
+        }
+    }
+}
+
+impl Player for Learned {
+    /// Creates a player with an empty table, which plays randomly. Use
+    /// `with_table` to pass a trained table.
+    fn new(color: EdgeState, seed: u64) -> Self
+    where
+        Self: Sized
+    {
+        Self::with_table(color, seed, Arc::new(Mutex::new(ValueTable::new())))
+    }
+
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
+        if self.rng.gen_bool(self.exploration) {
+            return state.legal_moves().choose(&mut self.rng).ok_or(SimError::GameOver);
+        }
+
+        let own = state.mask(self.color);
+        let other = state.mask(self.color.opponent());
+        let table = self.table.lock().unwrap();
+
+        // Moves completing a triangle have value 0. Ties are broken randomly.
+        let mut best = Vec::new();
+        let mut best_value = -1.0;
+        for edge in state.legal_moves() {
+            let value = if state.would_create_triangle(edge, self.color) {
+                0.0
+            } else {
+                table.value(own | edge.bit(), other)
+            };
+
+            if value > best_value {
+                best.clear();
+                best_value = value;
+            }
+            if value == best_value {
+                best.push(edge);
+            }
+        }
+
+        best.into_iter().choose(&mut self.rng).ok_or(SimError::GameOver)
+    }
+
+    fn on_game_start(&mut self, state: &GameState) {
+        self.own = state.mask(self.color);
+        self.other = state.mask(self.color.opponent());
+        self.history.clear();
+    }
+
+    fn on_move(&mut self, edge: Edge, color: EdgeState) {
+        if color == self.color {
+            self.own |= edge.bit();
+            self.history.push((self.own, self.other, true));
+        } else {
+            self.other |= edge.bit();
+            self.history.push((self.other, self.own, false));
+        }
+    }
+
+    fn on_game_end(&mut self, winner: EdgeState) {
+        if let Some(learning_rate) = self.learning_rate {
+            self.learn(winner, learning_rate);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+    }
+}
@@ -1,16 +1,91 @@
 use super::Player;
-use crate::game::{Edge, EdgeState, GameState};
+use crate::game::{Edge, EdgeState, GameState, SimError};
 use crate::player::Random;
-use std::mem;
+use std::{fmt, mem, time::{Duration, Instant}};
 
-pub struct MiniMax(EdgeState);
+pub struct MiniMax {
+    color: EdgeState,
+
+    /// Whether to print what the player is thinking to stdout.
+    verbose: bool,
+
+    /// Whether to choose a random move when no winning move is known.
+    /// Otherwise the first safe move is chosen.
+    randomize: bool,
+
+    /// Number of positions expanded during the last search.
+    nodes: u64,
+
+    /// The time the next search may take (see `Player::set_time_budget`).
+    /// If the search takes longer, a fallback move is chosen.
+    time_budget: Option<Duration>,
+
+    /// Used to choose a move when no winning move is known.
+    random: Random,
+}
 
 impl MiniMax {
+    /// Enables or disables printing the player's thoughts to stdout (enabled
+    /// by default).
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Enables or disables choosing random moves when no winning move is
+    /// known (enabled by default).
+    pub fn set_randomize(&mut self, randomize: bool) {
+        self.randomize = randomize;
+    }
+
+    /// Returns the number of positions expanded while searching the last
+    /// move.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    fn report(&self, msg: fmt::Arguments) {
+        if self.verbose {
+            println!("{:?} {}", self.color, msg);
+        }
+    }
+
+    /// Prints how many positions were expanded since `start`.
+    fn report_nodes(&self, start: Instant) {
+        let secs = start.elapsed().as_secs_f64();
+        self.report(format_args!(
+            "expanded {} positions in {:.1}ms ({:.0} positions/s)",
+            self.nodes,
+            secs * 1000.0,
+            self.nodes as f64 / secs,
+        ));
+    }
+
+    /// Chooses a move without knowing whether it wins.
+    fn fallback_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
+        if self.randomize {
+            return self.random.next_move(state);
+        }
+
+        state.safe_moves(self.color)
+            .next()
+            .or_else(|| state.legal_moves().next())
+            .ok_or(SimError::GameOver)
+    }
+
     // Wanted to do recursive minimax function at first, but was worried about
     // the overhead, so this ugly thing came out in the end
-    fn mini_max_move(&mut self, state: &mut GameState) -> Edge {
+    #[allow(clippy::assign_op_pattern)]
+    fn mini_max_move(&mut self, state: &mut GameState) -> Result<Edge, SimError> {
+        let start = Instant::now();
+        let deadline = self.time_budget.take().and_then(|budget| start.checked_add(budget));
+        let original = state.clone();
+        self.nodes = 0;
+
         // gather our possible moves
-        let mut encoded_moves = MiniMax::get_encoded_moves(state);
+        let mut encoded_moves = state.legal_mask();
+        if encoded_moves == 0 {
+            return Err(SimError::GameOver);
+        }
 
         // gather all edges so we dont have to keep creating them on the fly
         let edges: [Edge; 15] = [
@@ -32,7 +107,7 @@ impl MiniMax {
         ];
 
         // For convenient access to player handles
-        let me = self.0;
+        let me = self.color;
         let other = if me == EdgeState::Red {
             EdgeState::Blue
         } else {
@@ -49,52 +124,30 @@ impl MiniMax {
 
         // First moves are all equal
         if pre_depth == 0 {
-            let startmove = Random::new(me).next_move(state);
-            if me == EdgeState::Blue {
-                println!(
-                    "Blue randomly choses starting move {}, expecting to lose",
-                    startmove.id()
-                );
-            } else {
-                println!(
-                    "Red randomly choses starting move {}, expecting to lose",
-                    startmove.id()
-                );
-            }
-            return startmove;
+            let startmove = self.fallback_move(state)?;
+            self.report(format_args!(
+                "randomly choses starting move {}, expecting to lose",
+                startmove.id(),
+            ));
+            return Ok(startmove);
         } else if pre_depth == 1 {
             // Second moves always win (?)
-            let secondmove = Random::new(me).next_move(state);
-            if me == EdgeState::Blue {
-                println!(
-                    "Blue thinks: second move always wins, chooses randomly: {}",
-                    secondmove.id()
-                );
-            } else {
-                println!(
-                    "Red thinks: second move always wins, chooses randomly: {}",
-                    secondmove.id()
-                );
-            }
-            return secondmove;
+            let secondmove = self.fallback_move(state)?;
+            self.report(format_args!(
+                "thinks: second move always wins, chooses randomly: {}",
+                secondmove.id(),
+            ));
+            return Ok(secondmove);
         } else if pre_depth == 2 {
             // If second moves always win, we can choose random here and hope for a mistake
             // TODO this can probably improved so we dont do the worst move right away
-            let thirdmove = Random::new(me).next_move(state);
-            if me == EdgeState::Blue {
-                println!(
-                    "Blue knows no winning move, choses randomly: {} (at depth {})",
-                    thirdmove.id(),
-                    pre_depth
-                );
-            } else {
-                println!(
-                    "Red knows no winning move, choses randomly: {} (at depth {})",
-                    thirdmove.id(),
-                    pre_depth
-                );
-            }
-            return thirdmove;
+            let thirdmove = self.fallback_move(state)?;
+            self.report(format_args!(
+                "knows no winning move, choses randomly: {} (at depth {})",
+                thirdmove.id(),
+                pre_depth,
+            ));
+            return Ok(thirdmove);
         }
 
         // Using this to track our progression through game rounds/tree depth
@@ -133,9 +186,6 @@ impl MiniMax {
         // from true -> false (definite minimum) and from false -> true (definite maximum)
         let mut minimax = [true; 15];
 
-        // Track amount of expanded positions
-        // let mut counter: u64 = 0;
-
         // Now descend depth-first through the move sequence tree and let the leaf results
         // propagate upwards our minimax structure
         loop {
@@ -147,21 +197,13 @@ impl MiniMax {
             if depth == pre_depth && ascend {
                 // but only if we are sure we found a winning move
                 if minimax[depth] {
-                    if me == EdgeState::Blue {
-                        println!(
-                            "Blue knows the winning move: {} (at depth {})",
-                            move_sequence[depth],
-                            pre_depth
-                        );
-                    } else {
-                        println!(
-                            "Red knows the winning move: {} (at depth {})",
-                            move_sequence[depth],
-                            pre_depth
-                        );
-                    }
-                    // println!("Expanded positions {}", counter);
-                    return edges[move_sequence[depth] as usize];
+                    self.report(format_args!(
+                        "knows the winning move: {} (at depth {})",
+                        move_sequence[depth],
+                        pre_depth,
+                    ));
+                    self.report_nodes(start);
+                    return Ok(edges[move_sequence[depth] as usize]);
                 }
             }
 
@@ -184,7 +226,22 @@ impl MiniMax {
                 samelevel = false;
             } else {
                 // If we descended, we reinit result tracking for this layer
-                // counter += 1;
+                self.nodes += 1;
+
+                // Give up if the time is running out (the clock is only read
+                // every few positions because that's comparatively slow)
+                if self.nodes.is_multiple_of(1024)
+                    && deadline.is_some_and(|d| Instant::now() >= d)
+                {
+                    let timeout_move = self.fallback_move(&original)?;
+                    self.report(format_args!(
+                        "ran out of time, choses randomly: {} (at depth {})",
+                        timeout_move.id(),
+                        pre_depth,
+                    ));
+                    self.report_nodes(start);
+                    return Ok(timeout_move);
+                }
                 move_sequence[depth] = 0;
                 minimax[depth] = acting != me;
             }
@@ -204,22 +261,14 @@ impl MiniMax {
                 ascend = true;
                 // If we are out of moves at our starting depth, we didnt find a winning move
                 if depth == pre_depth {
-                    let randmove = Random::new(me).next_move(state);
-                    if me == EdgeState::Blue {
-                        println!(
-                            "Blue knows no winning move, choses randomly: {} (at depth {})",
-                            randmove.id(),
-                            pre_depth
-                        );
-                    } else {
-                        println!(
-                            "Red knows no winning move, choses randomly: {} (at depth {})",
-                            randmove.id(),
-                            pre_depth
-                        );
-                    }
-                    // println!("Expanded positions {}", counter);
-                    return Random::new(me).next_move(state);
+                    let randmove = self.fallback_move(state)?;
+                    self.report(format_args!(
+                        "knows no winning move, choses randomly: {} (at depth {})",
+                        randmove.id(),
+                        pre_depth,
+                    ));
+                    self.report_nodes(start);
+                    return Ok(randmove);
                 }
             }
 
@@ -253,28 +302,29 @@ impl MiniMax {
             mem::swap(&mut acting, &mut waiting);
         }
     }
-
-    fn get_encoded_moves(state: &GameState) -> u16 {
-        let mut encoded: u16 = 0;
-        for index in 0..15 {
-            if state.edge_state(Edge::new(index)) == EdgeState::None {
-                encoded = encoded | (1 << index);
-            }
-        }
-        encoded
-    }
 }
 
 impl Player for MiniMax {
-    fn new(color: EdgeState) -> Self
+    fn new(color: EdgeState, seed: u64) -> Self
     where
         Self: Sized,
     {
-        Self(color)
+        Self {
+            color,
+            verbose: true,
+            randomize: true,
+            nodes: 0,
+            time_budget: None,
+            random: Random::new(color, seed),
+        }
     }
 
-    fn next_move(&mut self, state: &GameState) -> Edge {
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
         let mut state_copy = state.clone();
         self.mini_max_move(&mut state_copy)
     }
+
+    fn set_time_budget(&mut self, budget: Duration) {
+        self.time_budget = Some(budget);
+    }
 }
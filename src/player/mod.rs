@@ -1,20 +1,76 @@
-use crate::game::{GameState, Edge, EdgeState};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
 
+use std::time::Duration;
+
+use crate::game::{GameState, Edge, EdgeState, SimError};
+
+mod book;
+mod config;
+mod heuristic;
+mod learned;
 mod random;
 mod minimax;
 
+pub use book::Book;
+pub use config::{
+    PlayerConfig, RandomConfig, MiniMaxConfig, BookConfig, LearnedConfig, HeuristicConfig,
+    PLAYER_HELP,
+};
+pub use heuristic::Heuristic;
+pub use learned::Learned;
 pub use random::{Random, DumbRandom};
 pub use minimax::{MiniMax};
 
+/// The random number generator used for everything that derives its
+/// randomness from a seed. Unlike `rand::rngs::StdRng`, its output for a
+/// given seed doesn't change between versions and platforms, so recorded
+/// seeds reproduce games exactly.
+pub type SeededRng = ChaChaRng;
+
+/// Returns the generator for `seed`.
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
+
+/// Derives the seeds for the red and the blue player from the seed of a
+/// game.
+pub fn derive_seeds(seed: u64) -> (u64, u64) {
+    let mut rng = seeded_rng(seed);
+    (rng.gen(), rng.gen())
+}
+
 /// The interface for all non-human players.
-pub(crate) trait Player {
-    /// Create a new instance of the player.
-    fn new(color: EdgeState) -> Self
+pub trait Player {
+    /// Create a new instance of the player. Players that make random
+    /// decisions must derive all randomness from `seed`, so that games can be
+    /// reproduced.
+    fn new(color: EdgeState, seed: u64) -> Self
     where
         Self: Sized;
 
     /// Return a new move (which edge to be colored).
     ///
-    /// `state` is guaranteed to still have uncolored edges left.
-    fn next_move(&mut self, state: &GameState) -> Edge;
+    /// Returns `SimError::GameOver` if there are no legal moves left in
+    /// `state`.
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError>;
+
+    /// Called before every `next_move` in games with a time control (see
+    /// `clock::Clock::move_budget`) with the time the player should spend on
+    /// the move at most. Players that take longer may lose on time.
+    fn set_time_budget(&mut self, _budget: Duration) {}
+
+    /// Called before the first move of a game with the starting position.
+    fn on_game_start(&mut self, _state: &GameState) {}
+
+    /// Called after every move (of both players) with the colored edge and
+    /// the color of the player who made the move.
+    fn on_move(&mut self, _edge: Edge, _color: EdgeState) {}
+
+    /// Called when the game is over.
+    fn on_game_end(&mut self, _winner: EdgeState) {}
+
+    /// Called before a player is reused for another game. Players should
+    /// forget everything that's only valid for one game.
+    fn reset(&mut self) {}
 }
@@ -1,53 +1,57 @@
-use rand::{thread_rng, seq::IteratorRandom};
+use rand::seq::IteratorRandom;
 
-use crate::game::{GameState, Edge, EdgeState};
-use super::Player;
+use crate::game::{GameState, Edge, EdgeState, SimError};
+use super::{Player, SeededRng, seeded_rng};
 
 /// A random player which chooses some completely random edge. Even if that
 /// edge will make this player loose the game.
-pub struct DumbRandom;
+pub struct DumbRandom(SeededRng);
 
 impl Player for DumbRandom {
-    fn new(_: EdgeState) -> Self
+    fn new(_: EdgeState, seed: u64) -> Self
     where
         Self: Sized
     {
-        Self
+        Self(seeded_rng(seed))
     }
 
-    fn next_move(&mut self, state: &GameState) -> Edge {
-        random_available_move(state)
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
+        random_available_move(state, &mut self.0)
     }
 }
 
 /// A random player that always chooses a edge that won't lead to immediate
 /// loss if such an edge is available.
-pub struct Random(EdgeState);
+pub struct Random {
+    color: EdgeState,
+    rng: SeededRng,
+}
 
 impl Player for Random {
-    fn new(color: EdgeState) -> Self
+    fn new(color: EdgeState, seed: u64) -> Self
     where
         Self: Sized
     {
-        Self(color)
+        Self {
+            color,
+            rng: seeded_rng(seed),
+        }
     }
 
-    fn next_move(&mut self, state: &GameState) -> Edge {
+    fn next_move(&mut self, state: &GameState) -> Result<Edge, SimError> {
         // First try to find an edge that won't lead to loosing the game. If
         // that's not possible, just take a random other one.
-        Edge::all_edges()
-            .filter(|e| state.edge_state(*e).is_none())
-            .filter(|e| !state.would_create_triangle(*e, self.0))
-            .choose(&mut thread_rng())
-            .unwrap_or(random_available_move(state))
+        match state.safe_moves(self.color).choose(&mut self.rng) {
+            Some(edge) => Ok(edge),
+            None => random_available_move(state, &mut self.rng),
+        }
     }
 }
 
-/// Returns a random edge that is still `None`. Panics if all edges in `state`
-/// are already colored.
-fn random_available_move(state: &GameState) -> Edge {
-    Edge::all_edges()
-        .filter(|e| state.edge_state(*e).is_none())
-        .choose(&mut thread_rng())
-        .unwrap()
+/// Returns a random legal move or `SimError::GameOver` if there are no legal
+/// moves left in `state`.
+fn random_available_move(state: &GameState, rng: &mut SeededRng) -> Result<Edge, SimError> {
+    state.legal_moves()
+        .choose(rng)
+        .ok_or(SimError::GameOver)
 }
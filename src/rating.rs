@@ -0,0 +1,170 @@
+//! Elo ratings of player configurations, kept across tournaments.
+//!
+//! Ratings are stored in a plain text file with one player per line:
+//! `<elo> <games> <wins> <player>`, where `<player>` is the player
+//! configuration as given on the command line (e.g. `minimax:randomize=false`)
+//! and may contain spaces. The file is a data file (see `io`).
+
+use std::{collections::HashMap, path::Path};
+
+use crate::runner::GameResult;
+use crate::game::EdgeState;
+use crate::io::{self, DataFile};
+
+
+/// The rating of a player that hasn't played any game yet.
+pub const INITIAL_ELO: f64 = 1500.0;
+
+/// How much a single game changes the ratings.
+pub const K_FACTOR: f64 = 20.0;
+
+/// The rating and statistics of one player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    pub elo: f64,
+    pub games: u32,
+    pub wins: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Self {
+            elo: INITIAL_ELO,
+            games: 0,
+            wins: 0,
+        }
+    }
+}
+
+/// Returns the expected score (between 0 and 1) of a player rated `elo`
+/// against a player rated `opponent_elo`.
+pub fn expected_score(elo: f64, opponent_elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_elo - elo) / 400.0))
+}
+
+/// The ratings of all players that have played so far.
+#[derive(Clone, Debug, Default)]
+pub struct Ratings {
+    players: HashMap<String, Rating>,
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads ratings from `path`. If the file doesn't exist, no player is
+    /// rated yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = match DataFile::read_if_exists(path)? {
+            Some(file) => file,
+            None => return Ok(Self::new()),
+        };
+
+        let mut out = Self::new();
+        for line in file.lines() {
+            // The player name may contain spaces, so it's everything after
+            // the third field.
+            let invalid = || line.invalid();
+            let mut fields = [""; 4];
+            let mut rest = line.text;
+            for field in &mut fields[..3] {
+                let (value, tail) = rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+                *field = value;
+                rest = tail.trim_start();
+            }
+            fields[3] = rest;
+
+            let elo = fields[0].parse::<f64>().ok()
+                .filter(|elo| elo.is_finite())
+                .ok_or_else(invalid)?;
+            let rating = Rating {
+                elo,
+                games: fields[1].parse().map_err(|_| invalid())?,
+                wins: fields[2].parse().map_err(|_| invalid())?,
+            };
+            out.players.insert(fields[3].to_string(), rating);
+        }
+
+        Ok(out)
+    }
+
+    /// Writes all ratings to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut out = String::from("# Sim ratings: <elo> <games> <wins> <player>\n");
+        for (player, rating) in self.leaderboard() {
+            out += &format!("{:.1} {} {} {}\n", rating.elo, rating.games, rating.wins, player);
+        }
+
+        io::write(path, &out)
+    }
+
+    /// Returns the rating of `player` (the initial rating if the player
+    /// hasn't played yet).
+    pub fn get(&self, player: &str) -> Rating {
+        self.players.get(player).cloned().unwrap_or_default()
+    }
+
+    /// Updates the ratings of both players with the result of one game.
+    pub fn record(&mut self, red: &str, blue: &str, result: &GameResult) {
+        let mut red_rating = self.get(red);
+        let mut blue_rating = self.get(blue);
+
+        let red_score = if result.winner == EdgeState::Red { 1.0 } else { 0.0 };
+        let change = K_FACTOR * (red_score - expected_score(red_rating.elo, blue_rating.elo));
+        red_rating.elo += change;
+        blue_rating.elo -= change;
+
+        red_rating.games += 1;
+        blue_rating.games += 1;
+        match result.winner {
+            EdgeState::Red => red_rating.wins += 1,
+            EdgeState::Blue => blue_rating.wins += 1,
+            EdgeState::None => {}
+        }
+
+        self.players.insert(red.to_string(), red_rating);
+        self.players.insert(blue.to_string(), blue_rating);
+    }
+
+    /// Returns all players sorted by rating, best first.
+    pub fn leaderboard(&self) -> Vec<(&str, Rating)> {
+        let mut out = self.players.iter()
+            .map(|(player, rating)| (player.as_str(), *rating))
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b| b.1.elo.total_cmp(&a.1.elo).then(a.0.cmp(b.0)));
+        out
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let mut ratings = Ratings::new();
+        ratings.players.insert("minimax".into(), Rating { elo: 1512.5, games: 3, wins: 2 });
+        ratings.players.insert(
+            "book:file=/tmp/my book,then=minimax".into(),
+            Rating { elo: 1487.5, games: 3, wins: 1 },
+        );
+
+        let path = env::temp_dir().join(format!("sim-ratings-{}", std::process::id()));
+        ratings.save(&path).unwrap();
+        let loaded = Ratings::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.leaderboard(), ratings.leaderboard());
+        assert_eq!(loaded.get("book:file=/tmp/my book,then=minimax").wins, 1);
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let path = env::temp_dir().join(format!("sim-ratings-missing-{}", std::process::id()));
+        assert!(Ratings::load(&path).unwrap().leaderboard().is_empty());
+    }
+}
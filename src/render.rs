@@ -0,0 +1,343 @@
+//! Drawing boards without a window: the sizes and geometry shared with the
+//! GUI, and rendering positions to SVG and PNG images (with the colors of a
+//! `Theme`).
+//!
+//! A position is first turned into a `Scene` (a list of lines and circles),
+//! which can then be written as SVG or rasterized to RGB pixels. Whole games
+//! are rendered as a sequence of scenes, one per move (see `game_scenes`),
+//! which can be written as animated GIF.
+
+use std::{f32::consts::PI, fs::{self, File}, io::BufWriter, path::Path};
+
+use crate::{
+    game::{Edge, GameState, Outcome, SimError, Vertex},
+    theme::Theme,
+};
+
+
+/// A color given by its red, green and blue components (between 0 and 1).
+pub type Rgb = [f32; 3];
+
+/// All sizes below are meant for a board of size 1000×1000 and are scaled
+/// according to the actual size (see `Geometry`).
+pub const REFERENCE_SIZE: f32 = 1000.0;
+
+/// The space between the board and the border of the image.
+pub const MARGIN: f32 = 50.0;
+
+/// The radius of the circles drawn for the vertices.
+pub const POINT_RADIUS: f32 = 10.0;
+
+/// The line widths of uncolored, red and blue edges. Blue edges are wider so
+/// that the colors can be told apart by their width as well.
+pub const UNCOLORED_WIDTH: f32 = 1.5;
+pub const RED_WIDTH: f32 = 4.0;
+pub const BLUE_WIDTH: f32 = 6.0;
+
+/// How much wider than the edge the outline of highlighted edges is.
+pub const HIGHLIGHT_WIDTH: f32 = 10.0;
+
+/// The number of vertices of the board.
+pub const NUM_VERTICES: usize = 6;
+
+/// The largest image size (width and height in pixels) accepted on the
+/// command line. A PNG of this size takes 64 MiB while it's drawn.
+pub const MAX_IMAGE_SIZE: u32 = 4096;
+
+/// Positions of the vertices for a board of a given size.
+///
+/// The vertices lie on a circle, forming a regular polygon with a flat top
+/// (for 6 vertices, a hexagon):
+///
+/// ```text
+///       _____
+///      /     \
+///     /       \
+///     \       /
+///      \_____/
+/// ```
+///
+/// The circle's radius is chosen such that there is a margin of `MARGIN`
+/// (scaled) between the board and the border.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Geometry {
+    pub width: f32,
+    pub height: f32,
+
+    /// Factor by which all sizes are scaled relative to a board of size
+    /// `REFERENCE_SIZE`.
+    pub scale: f32,
+
+    /// The positions of all vertices, indexed by vertex ID.
+    pub corners: Vec<(f32, f32)>,
+}
+
+impl Geometry {
+    pub fn new(width: f32, height: f32, num_vertices: usize) -> Self {
+        let scale = width.min(height) / REFERENCE_SIZE;
+        let radius = width.min(height) / 2.0 - MARGIN * scale;
+
+        // Start left of the top so that the top edge is horizontal.
+        let corners = (0..num_vertices)
+            .map(|i| {
+                let angle = -PI / 2.0 - PI / num_vertices as f32
+                    + 2.0 * PI * i as f32 / num_vertices as f32;
+                (width / 2.0 + angle.cos() * radius, height / 2.0 + angle.sin() * radius)
+            })
+            .collect();
+
+        Self { width, height, scale, corners }
+    }
+
+    pub fn corner(&self, v: Vertex) -> (f32, f32) {
+        self.corners[v.id() as usize]
+    }
+
+    /// Returns the endpoints of `e`.
+    pub fn line(&self, e: Edge) -> ((f32, f32), (f32, f32)) {
+        let (va, vb) = e.endpoints();
+        (self.corner(va), self.corner(vb))
+    }
+}
+
+/// Edges to emphasize when rendering a position.
+#[derive(Clone, Debug, Default)]
+pub struct Highlights {
+    /// The edge colored last, outlined with the selected color of the theme.
+    pub last_move: Option<Edge>,
+
+    /// A completed triangle, outlined with the warning color of the theme.
+    pub triangle: Option<[Vertex; 3]>,
+}
+
+/// A shape of a `Scene`.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    /// A line with round caps.
+    Line { from: (f32, f32), to: (f32, f32), width: f32, color: Rgb },
+    Circle { center: (f32, f32), radius: f32, color: Rgb },
+}
+
+/// Shapes to be drawn in order on a background.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub width: u32,
+    pub height: u32,
+    pub background: Rgb,
+    pub shapes: Vec<Shape>,
+}
+
+impl Scene {
+    /// Draws `state` on a square board of `size` pixels.
+    pub fn board(state: &GameState, size: u32, highlights: &Highlights, theme: &Theme) -> Self {
+        let geometry = Geometry::new(size as f32, size as f32, NUM_VERTICES);
+        let scale = geometry.scale;
+        let mut shapes = Vec::new();
+
+        // Outlines of highlighted edges go below the edges.
+        let triangle_edges = highlights.triangle
+            .map(|[a, b, c]| vec![Edge::between(a, b), Edge::between(b, c), Edge::between(a, c)])
+            .unwrap_or_default();
+        let outlines = triangle_edges.iter()
+            .map(|&e| (e, theme.warning))
+            .chain(highlights.last_move.map(|e| (e, theme.selected)));
+        for (e, color) in outlines {
+            let (from, to) = geometry.line(e);
+            let width = (theme.edge_style(state.edge_state(e)).1 + HIGHLIGHT_WIDTH) * scale;
+            shapes.push(Shape::Line { from, to, width, color });
+        }
+
+        // Uncolored edges first, so that they never cover colored ones.
+        // Dashed edges are split into one line per dash.
+        let mut edges = Edge::all_edges().collect::<Vec<_>>();
+        edges.sort_by_key(|&e| !state.edge_state(e).is_none());
+        for e in edges {
+            let (from, to) = geometry.line(e);
+            let (color, width) = theme.edge_style(state.edge_state(e));
+            let segments = match theme.dash(state.edge_state(e)) {
+                Some(dash) => dash.segments(from, to, scale),
+                None => vec![(from, to)],
+            };
+            for (from, to) in segments {
+                shapes.push(Shape::Line { from, to, width: width * scale, color });
+            }
+        }
+
+        for &center in &geometry.corners {
+            shapes.push(Shape::Circle { center, radius: POINT_RADIUS * scale, color: theme.point });
+        }
+
+        Self {
+            width: size,
+            height: size,
+            background: theme.background,
+            shapes,
+        }
+    }
+
+    /// Returns the scene as SVG document.
+    pub fn to_svg(&self) -> String {
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+                viewBox=\"0 0 {w} {h}\">\n",
+            w = self.width,
+            h = self.height,
+        );
+        out += &format!(
+            "  <rect width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            self.width,
+            self.height,
+            hex(self.background),
+        );
+
+        for shape in &self.shapes {
+            out += &match shape {
+                Shape::Line { from, to, width, color } => format!(
+                    "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" \
+                        stroke-width=\"{:.1}\" stroke-linecap=\"round\"/>\n",
+                    from.0, from.1, to.0, to.1, hex(*color), width,
+                ),
+                Shape::Circle { center, radius, color } => format!(
+                    "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\"/>\n",
+                    center.0, center.1, radius, hex(*color),
+                ),
+            };
+        }
+
+        out += "</svg>\n";
+        out
+    }
+
+    /// Rasterizes the scene (with anti-aliasing). Returns 3 bytes (red,
+    /// green, blue) per pixel, row by row.
+    pub fn to_rgb(&self) -> Vec<u8> {
+        let (w, h) = (self.width as usize, self.height as usize);
+        let mut pixels = vec![self.background; w * h];
+
+        for shape in &self.shapes {
+            // The distance of a pixel center to the shape's border (negative
+            // inside) and the bounding box of the shape.
+            let (color, bounds, distance): (_, _, Box<dyn Fn(f32, f32) -> f32>) = match *shape {
+                Shape::Line { from, to, width, color } => {
+                    let r = width / 2.0;
+                    let bounds = (from.0.min(to.0) - r, from.1.min(to.1) - r,
+                        from.0.max(to.0) + r, from.1.max(to.1) + r);
+                    (color, bounds, Box::new(move |x, y| segment_distance(from, to, (x, y)) - r))
+                }
+                Shape::Circle { center, radius, color } => {
+                    let bounds = (center.0 - radius, center.1 - radius,
+                        center.0 + radius, center.1 + radius);
+                    let distance = move |x: f32, y: f32| {
+                        ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt() - radius
+                    };
+                    (color, bounds, Box::new(distance))
+                }
+            };
+
+            let range = |min: f32, max: f32, len: usize| {
+                min.floor().max(0.0) as usize..(max.ceil() + 1.0).min(len as f32) as usize
+            };
+            for y in range(bounds.1, bounds.3, h) {
+                for x in range(bounds.0, bounds.2, w) {
+                    // Pixels within half a pixel of the border are partially
+                    // covered.
+                    let coverage = (0.5 - distance(x as f32 + 0.5, y as f32 + 0.5)).clamp(0.0, 1.0);
+                    let pixel = &mut pixels[y * w + x];
+                    for (p, c) in pixel.iter_mut().zip(&color) {
+                        *p += (c - *p) * coverage;
+                    }
+                }
+            }
+        }
+
+        pixels.iter()
+            .flat_map(|p| p.iter().map(|c| (c * 255.0).round() as u8))
+            .collect()
+    }
+
+    /// Writes the scene as SVG or PNG file, depending on the file extension
+    /// of `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let write_error = |e: &dyn std::fmt::Display| {
+            format!("failed to write '{}': {}", path.display(), e)
+        };
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => fs::write(path, self.to_svg()).map_err(|e| write_error(&e)),
+            Some("png") => {
+                let file = File::create(path).map_err(|e| write_error(&e))?;
+                let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+                png::HasParameters::set(&mut encoder, png::ColorType::RGB);
+                png::HasParameters::set(&mut encoder, png::BitDepth::Eight);
+                encoder.write_header()
+                    .and_then(|mut writer| writer.write_image_data(&self.to_rgb()))
+                    .map_err(|e| write_error(&e))
+            }
+            _ => Err(format!(
+                "unsupported image format of '{}' (supported: '.svg', '.png')",
+                path.display(),
+            )),
+        }
+    }
+}
+
+/// Returns one scene per position of the game given by `moves`: the empty
+/// board and the position after each move, with that move highlighted. If
+/// the game is over, the losing triangle is highlighted in the last scene.
+pub fn game_scenes(moves: &[Edge], size: u32, theme: &Theme) -> Result<Vec<Scene>, SimError> {
+    let mut state = GameState::new();
+    let mut out = vec![Scene::board(&state, size, &Highlights::default(), theme)];
+    for &edge in moves {
+        let mut highlights = Highlights {
+            last_move: Some(edge),
+            triangle: None,
+        };
+        if let Outcome::Won(winner) = state.play(edge)? {
+            highlights.triangle = state.find_triangle(winner.opponent());
+        }
+        out.push(Scene::board(&state, size, &highlights, theme));
+    }
+
+    Ok(out)
+}
+
+/// Writes `scenes` as animated GIF to `path`, which loops forever. Every
+/// scene is shown for `delay` hundredths of a second, the last one for three
+/// times as long.
+pub fn save_gif(scenes: &[Scene], path: &Path, delay: u16) -> Result<(), String> {
+    let write_error = |e: &dyn std::fmt::Display| {
+        format!("failed to write '{}': {}", path.display(), e)
+    };
+    let (width, height) = match scenes.first() {
+        Some(scene) => (scene.width as u16, scene.height as u16),
+        None => return Err("no frames to write".into()),
+    };
+
+    let file = File::create(path).map_err(|e| write_error(&e))?;
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[])
+        .map_err(|e| write_error(&e))?;
+    gif::SetParameter::set(&mut encoder, gif::Repeat::Infinite).map_err(|e| write_error(&e))?;
+
+    for (i, scene) in scenes.iter().enumerate() {
+        let mut frame = gif::Frame::from_rgb(width, height, &scene.to_rgb());
+        frame.delay = if i + 1 == scenes.len() { delay.saturating_mul(3) } else { delay };
+        encoder.write_frame(&frame).map_err(|e| write_error(&e))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the color in the `#rrggbb` notation.
+fn hex(color: Rgb) -> String {
+    let [r, g, b] = color.map(|c| (c * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Calculates the nearest distance of the point `p` to the line segment from
+/// `a` to `b`.
+fn segment_distance(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let (apx, apy) = (p.0 - a.0, p.1 - a.1);
+    let t = ((abx * apx + aby * apy) / (abx * abx + aby * aby)).clamp(0.0, 1.0);
+    ((apx - abx * t).powi(2) + (apy - aby * t).powi(2)).sqrt()
+}
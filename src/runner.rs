@@ -0,0 +1,169 @@
+//! Playing games between computer players without a GUI.
+
+use std::time::Instant;
+
+use crate::{
+    clock::{Clock, TimeControl},
+    game::{GameState, Edge, EdgeState, Outcome},
+    player::Player,
+};
+
+
+/// Why a player lost the game without completing a triangle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forfeit {
+    /// The player ran out of time.
+    Time,
+    /// The (computer) player failed to make a move or made an illegal one.
+    InvalidMove,
+}
+
+/// The result of a finished game.
+#[derive(Clone, Debug)]
+pub struct GameResult {
+    pub winner: EdgeState,
+    pub moves: Vec<Edge>,
+
+    /// Why the loser forfeited the game, if it didn't end with a triangle.
+    pub forfeit: Option<Forfeit>,
+}
+
+/// Plays one game between the two given players, starting from the empty
+/// board. All lifecycle hooks of the players are called. With a time
+/// control, a player who takes too long for a move loses the game. A player
+/// who fails to make a move or makes an illegal move loses as well.
+pub fn play_game(
+    red: &mut dyn Player,
+    blue: &mut dyn Player,
+    time_control: Option<TimeControl>,
+) -> GameResult {
+    let mut state = GameState::new();
+    let mut moves = Vec::new();
+    let mut clock = time_control.map(Clock::new);
+
+    red.on_game_start(&state);
+    blue.on_game_start(&state);
+
+    loop {
+        let color = state.to_move();
+        let player: &mut dyn Player = match color {
+            EdgeState::Blue => &mut *blue,
+            _ => &mut *red,
+        };
+        if let Some(clock) = &clock {
+            player.set_time_budget(clock.move_budget(color, &state));
+        }
+        let start = Instant::now();
+        let edge = player.next_move(&state);
+
+        if let Some(clock) = &mut clock {
+            if !clock.record_move(color, start.elapsed()) {
+                return end_game(red, blue, color.opponent(), moves, Some(Forfeit::Time));
+            }
+        }
+
+        let outcome = match edge.and_then(|edge| Ok((edge, state.play(edge)?))) {
+            Ok((edge, outcome)) => {
+                moves.push(edge);
+                red.on_move(edge, color);
+                blue.on_move(edge, color);
+                outcome
+            }
+            Err(_) => {
+                let forfeit = Some(Forfeit::InvalidMove);
+                return end_game(red, blue, color.opponent(), moves, forfeit);
+            }
+        };
+
+        if let Outcome::Won(winner) = outcome {
+            return end_game(red, blue, winner, moves, None);
+        }
+    }
+}
+
+/// Tells both players that `winner` won and returns the result.
+fn end_game(
+    red: &mut dyn Player,
+    blue: &mut dyn Player,
+    winner: EdgeState,
+    moves: Vec<Edge>,
+    forfeit: Option<Forfeit>,
+) -> GameResult {
+    red.on_game_end(winner);
+    blue.on_game_end(winner);
+    GameResult { winner, moves, forfeit }
+}
+
+/// Plays `games` games between the same two players (with the same time
+/// control). The players are reset before every game. Returns the results of
+/// all games.
+pub fn play_match(
+    red: &mut dyn Player,
+    blue: &mut dyn Player,
+    games: u32,
+    time_control: Option<TimeControl>,
+) -> Vec<GameResult> {
+    (0..games)
+        .map(|_| {
+            red.reset();
+            blue.reset();
+            play_game(red, blue, time_control)
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::SimError, player::Random};
+    use super::*;
+
+    /// Always colors edge 0, which is illegal from its second move on.
+    struct Stubborn;
+
+    impl Player for Stubborn {
+        fn new(_color: EdgeState, _seed: u64) -> Self {
+            Stubborn
+        }
+
+        fn next_move(&mut self, _state: &GameState) -> Result<Edge, SimError> {
+            Ok(Edge::new(0))
+        }
+    }
+
+    /// Never finds a move.
+    struct Failing;
+
+    impl Player for Failing {
+        fn new(_color: EdgeState, _seed: u64) -> Self {
+            Failing
+        }
+
+        fn next_move(&mut self, _state: &GameState) -> Result<Edge, SimError> {
+            Err(SimError::GameOver)
+        }
+    }
+
+    #[test]
+    fn illegal_move_forfeits() {
+        let mut red = Stubborn;
+        let mut blue = Random::new(EdgeState::Blue, 1);
+        let result = play_game(&mut red, &mut blue, None);
+        assert_eq!(result.winner, EdgeState::Blue);
+        assert_eq!(result.forfeit, Some(Forfeit::InvalidMove));
+        assert_eq!(result.moves.len(), 2);
+    }
+
+    #[test]
+    fn failed_move_forfeits_and_match_continues() {
+        let mut red = Random::new(EdgeState::Red, 1);
+        let mut blue = Failing;
+        let results = play_match(&mut red, &mut blue, 3, None);
+        assert_eq!(results.len(), 3);
+        for result in &results {
+            assert_eq!(result.winner, EdgeState::Red);
+            assert_eq!(result.forfeit, Some(Forfeit::InvalidMove));
+            assert_eq!(result.moves.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Colors and dash patterns used to draw boards (in the GUI and by `render`).
+//!
+//! There are built-in themes (see `THEME_NAMES`) and theme files. A theme
+//! file contains lines of the form `key: value`:
+//! - `base`: the built-in theme used for all keys not in the file. Must be
+//!   the first key if given (default: `dark`).
+//! - `background`, `uncolored`, `red`, `blue`, `selected`, `warning` and
+//!   `point` (also used for text): colors in the `#rrggbb` notation.
+//! - `red_dash` and `blue_dash`: the dash pattern of the player's edges,
+//!   given as the length of the dashes and the gaps (e.g. `24 16`, scaled
+//!   like all sizes; dashes must be at least 1 long), or `none` for solid
+//!   lines.
+//!
+//! A theme file is a data file (see `io`).
+
+use std::{path::Path, str::FromStr};
+
+use crate::{
+    game::EdgeState,
+    io::DataFile,
+    render::{Rgb, BLUE_WIDTH, RED_WIDTH, UNCOLORED_WIDTH},
+};
+
+
+/// The names of all built-in themes.
+pub const THEME_NAMES: [&str; 4] = ["dark", "light", "high-contrast", "colorblind"];
+
+/// A dash pattern. Both lengths are scaled like all sizes (see
+/// `render::Geometry`). Since dashes have round caps, gaps look shorter than
+/// `off` by the line width.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dash {
+    pub on: f32,
+    pub off: f32,
+}
+
+impl Dash {
+    /// Splits the line from `from` to `to` into its dashes, with the lengths
+    /// multiplied by `scale`.
+    pub fn segments(
+        &self,
+        from: (f32, f32),
+        to: (f32, f32),
+        scale: f32,
+    ) -> Vec<((f32, f32), (f32, f32))> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let len = (dx * dx + dy * dy).sqrt();
+        let point = |s: f32| (from.0 + dx * s / len, from.1 + dy * s / len);
+
+        let (on, period) = (self.on * scale, (self.on + self.off) * scale);
+        let mut out = Vec::new();
+        let mut s = 0.0;
+        while s < len {
+            out.push((point(s), point((s + on).min(len))));
+            s += period;
+        }
+        out
+    }
+}
+
+/// All colors and dash patterns used to draw a board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub background: Rgb,
+    pub uncolored: Rgb,
+    pub red: Rgb,
+    pub blue: Rgb,
+
+    /// Used to highlight selected edges and the last move.
+    pub selected: Rgb,
+
+    /// Used to highlight losing edges and completed triangles.
+    pub warning: Rgb,
+
+    /// The color of the vertices and of text.
+    pub point: Rgb,
+
+    /// Dash patterns of the edges of each player (solid if `None`).
+    pub red_dash: Option<Dash>,
+    pub blue_dash: Option<Dash>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin("dark").unwrap()
+    }
+}
+
+impl Theme {
+    /// Returns the built-in theme with the given name (see `THEME_NAMES`).
+    pub fn builtin(name: &str) -> Option<Self> {
+        let dark = Self {
+            background: [0.0, 0.0, 0.0],
+            uncolored: [0.6, 0.6, 0.6],
+            red: [0.9, 0.29, 0.23],
+            blue: [0.3, 0.49, 1.0],
+            selected: [0.6, 0.8, 0.6],
+            warning: [1.0, 0.65, 0.0],
+            point: [1.0, 1.0, 1.0],
+            red_dash: None,
+            blue_dash: None,
+        };
+
+        match name {
+            "dark" => Some(dark),
+            "light" => Some(Self {
+                background: [1.0, 1.0, 1.0],
+                uncolored: [0.7, 0.7, 0.7],
+                red: [0.8, 0.15, 0.1],
+                blue: [0.1, 0.3, 0.85],
+                selected: [0.3, 0.65, 0.3],
+                warning: [0.95, 0.55, 0.0],
+                point: [0.15, 0.15, 0.15],
+                ..dark
+            }),
+            "high-contrast" => Some(Self {
+                uncolored: [0.5, 0.5, 0.5],
+                red: [1.0, 0.25, 0.25],
+                blue: [0.35, 0.75, 1.0],
+                selected: [0.0, 1.0, 0.0],
+                warning: [1.0, 1.0, 0.0],
+                ..dark
+            }),
+            // Colors from the Okabe-Ito palette, which can be told apart with
+            // all common kinds of color blindness. Blue edges are dashed in
+            // addition.
+            "colorblind" => Some(Self {
+                red: [0.84, 0.37, 0.0],
+                blue: [0.34, 0.71, 0.91],
+                selected: [0.0, 0.62, 0.45],
+                warning: [0.94, 0.89, 0.26],
+                blue_dash: Some(Dash { on: 24.0, off: 16.0 }),
+                ..dark
+            }),
+            _ => None,
+        }
+    }
+
+    /// Reads a theme file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = DataFile::read(path)?;
+        let mut out = Self::default();
+        let mut first = true;
+        for line in file.lines() {
+            let invalid = || line.invalid();
+            let (key, value) = line.key_value()?;
+            match key {
+                "base" if first => out = Self::builtin(value).ok_or_else(invalid)?,
+                "background" => out.background = parse_color(value).ok_or_else(invalid)?,
+                "uncolored" => out.uncolored = parse_color(value).ok_or_else(invalid)?,
+                "red" => out.red = parse_color(value).ok_or_else(invalid)?,
+                "blue" => out.blue = parse_color(value).ok_or_else(invalid)?,
+                "selected" => out.selected = parse_color(value).ok_or_else(invalid)?,
+                "warning" => out.warning = parse_color(value).ok_or_else(invalid)?,
+                "point" => out.point = parse_color(value).ok_or_else(invalid)?,
+                "red_dash" => out.red_dash = parse_dash(value).ok_or_else(invalid)?,
+                "blue_dash" => out.blue_dash = parse_dash(value).ok_or_else(invalid)?,
+                _ => return Err(invalid()),
+            }
+            first = false;
+        }
+
+        Ok(out)
+    }
+
+    /// Returns the color and line width of edges with the given state.
+    pub fn edge_style(&self, state: EdgeState) -> (Rgb, f32) {
+        match state {
+            EdgeState::None => (self.uncolored, UNCOLORED_WIDTH),
+            EdgeState::Red => (self.red, RED_WIDTH),
+            EdgeState::Blue => (self.blue, BLUE_WIDTH),
+        }
+    }
+
+    /// Returns the dash pattern of edges with the given state.
+    pub fn dash(&self, state: EdgeState) -> Option<Dash> {
+        match state {
+            EdgeState::None => None,
+            EdgeState::Red => self.red_dash,
+            EdgeState::Blue => self.blue_dash,
+        }
+    }
+}
+
+/// Parses the name of a built-in theme or the path of a theme file.
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match Self::builtin(input) {
+            Some(theme) => Ok(theme),
+            None if Path::new(input).exists() => Self::load(Path::new(input)),
+            None => Err(format!(
+                "'{}' is neither a built-in theme ({}) nor a theme file",
+                input,
+                THEME_NAMES.join(", "),
+            )),
+        }
+    }
+}
+
+/// Parses a color in the `#rrggbb` notation.
+fn parse_color(input: &str) -> Option<Rgb> {
+    let digits = input.strip_prefix('#').filter(|d| d.len() == 6 && d.is_ascii())?;
+    let component = |i: usize| {
+        u8::from_str_radix(&digits[i..i + 2], 16).ok().map(|c| c as f32 / 255.0)
+    };
+    Some([component(0)?, component(2)?, component(4)?])
+}
+
+/// Parses a dash pattern (`<on> <off>`) or `none`. Returns `None` if the
+/// input is invalid. Tiny lengths are rejected, as a line would be split into
+/// an enormous number of dashes.
+fn parse_dash(input: &str) -> Option<Option<Dash>> {
+    if input == "none" {
+        return Some(None);
+    }
+
+    let mut lengths = input.split_whitespace().map(|s| s.parse::<f32>().ok());
+    match (lengths.next(), lengths.next(), lengths.next()) {
+        (Some(Some(on)), Some(Some(off)), None) if on >= 1.0 && off >= 0.0 && on + off >= 1.0 => {
+            Some(Some(Dash { on, off }))
+        }
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dash_patterns() {
+        assert_eq!(parse_dash("none"), Some(None));
+        assert_eq!(parse_dash("24 16"), Some(Some(Dash { on: 24.0, off: 16.0 })));
+        assert_eq!(parse_dash("1 0"), Some(Some(Dash { on: 1.0, off: 0.0 })));
+
+        for input in &["", "24", "24 16 8", "x 16", "24 -1", "0 16", "NaN 16", "24 NaN"] {
+            assert_eq!(parse_dash(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn tiny_dashes() {
+        for input in &["1e-30 0", "0.5 0.5", "0.001 100", "1e-30 1e-30"] {
+            assert_eq!(parse_dash(input), None, "{}", input);
+        }
+    }
+}